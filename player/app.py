@@ -3,7 +3,7 @@ from flask import Flask, send_file, request
 import flask
 import json
 
-from puzzle_generator import generate_puzzle
+from puzzle_generator import generate_puzzle, get_share_formats
 
 app = Flask(__name__)
 puzzles = {}
@@ -28,6 +28,7 @@ def new_puzzle():
         "ratio": ratio,
         "puzzle_id": next_puzzle_id,
     }
+    result.update(get_share_formats(grid))
     next_puzzle_id += 1
 
     return flask.jsonify(**result)
@@ -2,9 +2,9 @@
 Puzzle generation routines for Akari.
 """
 
+import json
 import random
 import subprocess
-import os
 
 # Constants for tuning the generation
 INCREMENTAL_CONSTRAINT_REMOVAL_TRIES = 400
@@ -12,7 +12,6 @@ NUM_RANDOM_GRIDS_TO_TRY = 40
 CONSTRAINED_GRID_RATIO_THRESHOLD = 0.4
 
 # Binaries that do the heavy lifting of solving the puzzle
-GLUCOSE_PATH = "../solver/bin/glucose"
 AKARI_SOLVER_PATH = "../solver/bin/akari_solver"
 
 class Grid():
@@ -118,6 +117,13 @@ class Grid():
         self.set_num_constraints(cell_list, num_constrained)
         return is_unique(self)
 
+    def to_json(self):
+        return {
+            "height": self.height,
+            "width": self.width,
+            "rows": str(self).split("\n"),
+        }
+
     def __str__(self):
         return '\n'.join(
             ''.join(self.squares[(v, h)].to_canonical_string() for h in range(self.width))
@@ -201,15 +207,41 @@ class GridSquare():
     def __repr__(self):
         return str(self) + " at {}".format(self.location)
 
+class SolverServer():
+    """
+    A single akari_solver process in serve mode, kept alive between requests
+    instead of starting a new process for every uniqueness check. It uses the
+    solver's built-in SAT solver, so Glucose is not needed.
+    """
+    process = None
+    next_id = 0
+
+    def request(self, method, grid):
+        if self.process is None or self.process.poll() is not None:
+            self.process = subprocess.Popen([AKARI_SOLVER_PATH, "serve"], stdin=subprocess.PIPE,
+                                            stdout=subprocess.PIPE, universal_newlines=True)
+        self.next_id += 1
+        request = {"id": self.next_id, "method": method, "puzzle": grid.to_json()}
+        self.process.stdin.write(json.dumps(request) + "\n")
+        self.process.stdin.flush()
+        response = json.loads(self.process.stdout.readline())
+        if "error" in response:
+            raise RuntimeError(response["error"])
+        return response["result"]
+
+solver_server = SolverServer()
+
 def call_solver_for_uniqueness(grid):
-    env = dict(os.environ)
-    env["SAT_SOLVER"] = GLUCOSE_PATH
-    sp = subprocess.Popen([AKARI_SOLVER_PATH, "-u"], stdin=subprocess.PIPE,
-                          stdout=subprocess.PIPE, universal_newlines=True, env=env)
-    input_str = "{} {}\n{}".format(grid.height, grid.width, str(grid))
-    res, _ = sp.communicate(input=input_str)
-    return int(res) == 1
-    
+    return solver_server.request("unique", grid)["unique"]
+
+def get_share_formats(grid):
+    """
+    Returns the puzz.link URL, Tatham game ID and share code of the grid, as a
+    dict with the keys url, game_id and code.
+    """
+    return solver_server.request("share", grid)
+
+
 def generate_puzzle(height, width, density):
     best_grid = None
     best_ratio = 1
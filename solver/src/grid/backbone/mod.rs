@@ -0,0 +1,130 @@
+use super::{Grid, SatSolver, try_solve};
+use super::clues::walls_and_clues;
+use super::cnf_format::make_cnf_formula;
+use super::coords::Coord;
+use super::utils::{precompute_data, print_grid_to_string};
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod tests;
+
+/// Whether an empty square holds a light across the solutions of a puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// A light in every solution.
+    Forced,
+    /// A light in no solution.
+    Forbidden,
+    /// A light in some solutions but not in others.
+    Free,
+}
+
+/// Where the lights of a puzzle are settled, and where they are not.
+#[derive(Clone, Debug)]
+pub struct Backbone {
+    /// The width of the grid, which gives the row and column of each square.
+    pub width: i32,
+    /// The status of each square in row-major order, or None for walls.
+    pub squares: Vec<Option<Status>>,
+    /// The number of times the solver was called.
+    pub solver_calls: usize,
+}
+
+impl Backbone {
+    /// The squares with the given status, in row-major order.
+    pub fn with_status(&self, status: Status) -> Vec<Coord> {
+        (0..self.squares.len()).filter(|&x| self.squares[x] == Some(status))
+            .map(|x| Coord::from_index(x, self.width as usize))
+            .collect()
+    }
+
+    /// The status of the given square, or None for walls and squares outside the grid.
+    pub fn status(&self, square: Coord) -> Option<Status> {
+        if square.col >= self.width as usize {
+            return None;
+        }
+        self.squares.get(square.to_index(self.width as usize)).and_then(|&x| x)
+    }
+
+    /// Whether the lights are the same in every solution.
+    pub fn is_settled(&self) -> bool {
+        !self.squares.contains(&Some(Status::Free))
+    }
+}
+
+/**
+ * Works out, for every empty square, whether it is a light in all of the
+ * solutions of the puzzle, in none of them or only in some. The puzzle is
+ * solved once, and then each square whose status isn't known yet is
+ * assumed to differ from that first solution, by adding a unit clause. If
+ * that can't be solved, the square is settled, and the clause saying so is
+ * kept for the calls that follow. Otherwise, every square that differs
+ * between the two solutions is free, which usually settles many squares
+ * with one call. Only the walls and clues of the grid are looked at.
+ * Returns an error if the puzzle has no solution, or if the solver fails.
+ */
+pub fn find_backbone(grid: &Grid, solver: SatSolver) -> Result<Backbone, String> {
+    let data = precompute_data(walls_and_clues(grid));
+    let mut formula = make_cnf_formula(&data);
+    let first = try_solve(solver, &formula)?.ok_or_else(|| "Puzzle has no solution".to_string())?;
+    let first = positive_literals(&first);
+    let mut solver_calls = 1;
+
+    let variables: Vec<(usize, i32)> = (0..grid.contents.len())
+        .filter_map(|x| formula.variable_for(x).map(|var| (x, var)))
+        .collect();
+    let mut squares = vec![None; grid.contents.len()];
+    for &(loc, var) in variables.iter() {
+        if squares[loc].is_some() {
+            continue;
+        }
+        let light = first.contains(&var);
+        let literal = if light { var } else { -var };
+        solver_calls += 1;
+        match try_solve(solver, &formula.append_clause(vec![-literal]))? {
+            Some(other) => {
+                let other = positive_literals(&other);
+                for &(x, other_var) in variables.iter() {
+                    if first.contains(&other_var) != other.contains(&other_var) {
+                        squares[x] = Some(Status::Free);
+                    }
+                }
+            },
+            None => {
+                squares[loc] = Some(if light { Status::Forced } else { Status::Forbidden });
+                formula = formula.append_clause(vec![literal]);
+            }
+        }
+    }
+    Ok(Backbone { width: grid.width, squares, solver_calls })
+}
+
+/**
+ * Draws the backbone as rows of the puzzle, with the walls and clues as in
+ * print_grid_to_string, a * for a light in every solution, a ^ for a square
+ * that is never a light and a ? for a square that is a light in only some.
+ */
+pub fn render_backbone(grid: &Grid, backbone: &Backbone) -> String {
+    let walls = print_grid_to_string(&walls_and_clues(grid), false);
+    let mut out = String::with_capacity(walls.len() + grid.height as usize);
+    for (loc, c) in walls.chars().enumerate() {
+        out.push(match backbone.squares.get(loc).and_then(|&x| x) {
+            Some(Status::Forced) => '*',
+            Some(Status::Forbidden) => '^',
+            Some(Status::Free) => '?',
+            None => c,
+        });
+        if (loc as i32 + 1) % grid.width == 0 {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn positive_literals(result: &[i32]) -> HashSet<i32> {
+    result.iter().cloned().filter(|&x| x > 0).collect()
+}
@@ -0,0 +1,71 @@
+use super::*;
+use super::super::cnf_format::CnfFormula;
+use super::super::dpll::{self, solve};
+use super::super::utils::get_grid_from_string;
+
+fn coords(squares: &[(usize, usize)]) -> Vec<Coord> {
+    squares.iter().map(|&x| Coord::from(x)).collect()
+}
+
+#[test]
+fn test_backbone_of_unique_puzzle() {
+    let grid = get_grid_from_string("____3__X_", 3, 3).unwrap();
+    let backbone = find_backbone(&grid, solve).unwrap();
+    assert!(backbone.is_settled());
+    assert_eq!(coords(&[(0, 1), (1, 0), (1, 2)]), backbone.with_status(Status::Forced));
+    assert_eq!(coords(&[(0, 0), (0, 2), (2, 0), (2, 2)]), backbone.with_status(Status::Forbidden));
+    assert_eq!(Some(Status::Forced), backbone.status(Coord::new(1, 0)));
+    assert_eq!(None, backbone.status(Coord::new(1, 1)));
+    assert_eq!(None, backbone.status(Coord::new(2, 1)));
+    assert_eq!(None, backbone.status(Coord::new(0, 3)));
+    assert_eq!("^*^\n*3*\n^X^\n", render_backbone(&grid, &backbone));
+}
+
+#[test]
+fn test_backbone_of_ambiguous_puzzle() {
+    let grid = get_grid_from_string("0__X__", 1, 6).unwrap();
+    let backbone = find_backbone(&grid, solve).unwrap();
+    assert!(!backbone.is_settled());
+    assert_eq!(vec![None, Some(Status::Forbidden), Some(Status::Forced), None,
+                    Some(Status::Free), Some(Status::Free)], backbone.squares);
+    assert_eq!("0^*X??\n", render_backbone(&grid, &backbone));
+
+    let grid = get_grid_from_string("__\n__", 2, 2).unwrap();
+    let backbone = find_backbone(&grid, solve).unwrap();
+    assert_eq!(coords(&[(0, 0), (0, 1), (1, 0), (1, 1)]), backbone.with_status(Status::Free));
+    // The second solution differs from the first in every square.
+    assert_eq!(2, backbone.solver_calls);
+}
+
+#[test]
+fn test_backbone_ignores_lights_and_marks() {
+    let grid = get_grid_from_string("*__X__", 1, 6).unwrap();
+    let backbone = find_backbone(&grid, solve).unwrap();
+    assert_eq!(coords(&[(0, 0), (0, 1), (0, 2), (0, 4), (0, 5)]), backbone.with_status(Status::Free));
+}
+
+#[test]
+fn test_backbone_unsolvable() {
+    let grid = get_grid_from_string("_0_", 1, 3).unwrap();
+    assert_eq!("Puzzle has no solution", find_backbone(&grid, solve).unwrap_err());
+}
+
+/// Solves the first formula, and fails on every one after that.
+fn fails_after_first(formula: &CnfFormula) -> Result<Vec<i32>, String> {
+    if formula.clauses().iter().any(|x| x.len() == 1) {
+        Err("Error executing SAT solver".to_string())
+    } else {
+        dpll::solve(formula)
+    }
+}
+
+#[test]
+fn test_backbone_solver_failure() {
+    fn broken(_: &CnfFormula) -> Result<Vec<i32>, String> {
+        Err("Error executing SAT solver".to_string())
+    }
+    let grid = get_grid_from_string("__\n__", 2, 2).unwrap();
+    assert_eq!("Error executing SAT solver", find_backbone(&grid, broken).unwrap_err());
+    // A failure is not taken to mean that a square is settled.
+    assert_eq!("Error executing SAT solver", find_backbone(&grid, fails_after_first).unwrap_err());
+}
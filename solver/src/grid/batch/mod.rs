@@ -0,0 +1,174 @@
+use super::{Grid, SatSolver};
+use super::solve_puzzle_with_stats;
+use super::utils::precompute_data;
+
+use serde::{Serialize, Deserialize};
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+#[cfg(test)]
+mod tests;
+
+pub const CSV_HEADER: &str =
+    "id,height,width,outcome,unique,solved_by_rules,solver_calls,variables,clauses,elapsed_ms,error";
+
+/// A puzzle of a collection, or the reason it could not be read.
+pub struct BatchPuzzle {
+    pub id: String,
+    pub puzzle: Result<Grid, String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Solved,
+    /// The puzzle has no solution, or the SAT solver failed.
+    Unsolved,
+    /// The puzzle could not be read.
+    Invalid,
+}
+
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Solved => "solved",
+            Outcome::Unsolved => "unsolved",
+            Outcome::Invalid => "invalid",
+        }
+    }
+}
+
+/// How solving one puzzle of a collection went.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchRecord {
+    pub id: String,
+    /// The dimensions of the puzzle, or 0 if it could not be read.
+    pub height: i32,
+    pub width: i32,
+    pub outcome: Outcome,
+    /// Whether the solution is unique, if the puzzle was solved.
+    pub unique: Option<bool>,
+    pub solved_by_rules: bool,
+    pub solver_calls: usize,
+    pub variables: usize,
+    pub clauses: usize,
+    /// The time taken to solve the puzzle, whether or not it was solved.
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+impl BatchRecord {
+    /// The record as a line of CSV, in the order of CSV_HEADER.
+    pub fn to_csv(&self) -> String {
+        format!("{},{},{},{},{},{},{},{},{},{},{}",
+                csv_field(&self.id), self.height, self.width, self.outcome.name(),
+                self.unique.map_or(String::new(), |x| x.to_string()), self.solved_by_rules,
+                self.solver_calls, self.variables, self.clauses, self.elapsed_ms,
+                self.error.as_ref().map_or(String::new(), |x| csv_field(x)))
+    }
+}
+
+/**
+ * Splits a collection of puzzles into the text of each puzzle, along with the
+ * line it starts on. If one_per_line is set, every line that isn't blank is a
+ * puzzle. Otherwise, puzzles are separated by blank lines.
+ */
+pub fn split_collection(input: &str, one_per_line: bool) -> Vec<(usize, String)> {
+    let mut result: Vec<(usize, String)> = Vec::new();
+    let mut in_puzzle = false;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            in_puzzle = false;
+            continue;
+        }
+        if in_puzzle && !one_per_line {
+            let text = &mut result.last_mut().unwrap().1;
+            text.push_str(line);
+            text.push('\n');
+        } else {
+            result.push((idx + 1, format!("{}\n", line)));
+            in_puzzle = true;
+        }
+    }
+    result
+}
+
+/**
+ * Solves every puzzle with solve_puzzle_with_stats, spread over the given
+ * number of threads, and returns a record for each, in the same order as the
+ * puzzles. The solver must be safe to call from several threads at once.
+ */
+pub fn solve_batch(puzzles: &[BatchPuzzle], solver: SatSolver, depth: usize, threads: usize)
+        -> Vec<BatchRecord> {
+    let next = AtomicUsize::new(0);
+    let records = Mutex::new(vec![None; puzzles.len()]);
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, puzzles.len().max(1)) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                if idx >= puzzles.len() {
+                    break;
+                }
+                let record = solve_one(&puzzles[idx], solver, depth);
+                records.lock().unwrap()[idx] = Some(record);
+            });
+        }
+    });
+    records.into_inner().unwrap().into_iter().map(|x| x.unwrap()).collect()
+}
+
+fn solve_one(puzzle: &BatchPuzzle, solver: SatSolver, depth: usize) -> BatchRecord {
+    let mut record = BatchRecord {
+        id: puzzle.id.clone(),
+        height: 0,
+        width: 0,
+        outcome: Outcome::Invalid,
+        unique: None,
+        solved_by_rules: false,
+        solver_calls: 0,
+        variables: 0,
+        clauses: 0,
+        elapsed_ms: 0,
+        error: None,
+    };
+    let grid = match puzzle.puzzle {
+        Ok(ref x) => x,
+        Err(ref e) => {
+            record.error = Some(e.clone());
+            return record;
+        }
+    };
+    record.height = grid.height;
+    record.width = grid.width;
+    let start = Instant::now();
+    let mut grid = precompute_data(grid.clone());
+    let result = solve_puzzle_with_stats(&mut grid, solver, depth);
+    record.elapsed_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(solution) => {
+            record.outcome = Outcome::Solved;
+            record.unique = Some(solution.unique);
+            record.solved_by_rules = solution.stats.solved_by_rules;
+            record.solver_calls = solution.stats.solver_calls;
+            record.variables = solution.stats.variables;
+            record.clauses = solution.stats.clauses;
+        },
+        Err(e) => {
+            record.outcome = Outcome::Unsolved;
+            record.error = Some(e);
+        }
+    }
+    record
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
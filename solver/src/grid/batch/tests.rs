@@ -0,0 +1,86 @@
+use super::*;
+use super::super::dpll::solve;
+use super::super::utils::get_grid_from_string;
+
+const TEST_GRID_STR: &str = "\
+__1_1__
+_______
+X_____2
+_______
+0_____0
+_______
+__1_0__";
+
+fn puzzle(id: &str, input: &str, height: i32, width: i32) -> BatchPuzzle {
+    BatchPuzzle { id: id.to_string(), puzzle: get_grid_from_string(input, height, width) }
+}
+
+#[test]
+fn test_split_collection() {
+    let input = "3 3\n___\n_3_\n_X_\n\n\n1 2\n_1\n  \n2 1\n_\n_";
+    assert_eq!(vec![(1, "3 3\n___\n_3_\n_X_\n".to_string()),
+                    (7, "1 2\n_1\n".to_string()),
+                    (10, "2 1\n_\n_\n".to_string())],
+               split_collection(input, false));
+    assert_eq!(vec![(1, "3x3:d3bBa\n".to_string()), (3, "1x1:a\n".to_string())],
+               split_collection("3x3:d3bBa\n\n1x1:a\n", true));
+    assert!(split_collection("\n \n", false).is_empty());
+}
+
+#[test]
+fn test_solve_batch() {
+    let puzzles = vec![
+        puzzle("unique", TEST_GRID_STR, 7, 7),
+        puzzle("ambiguous", "__\n__", 2, 2),
+        BatchPuzzle { id: "broken".to_string(), puzzle: Err("Unexpected square".to_string()) },
+        puzzle("unsolvable", "4", 1, 1),
+        puzzle("rules", "____3__X_", 3, 3),
+    ];
+    let records = solve_batch(&puzzles, solve, 0, 3);
+    let ids: Vec<&str> = records.iter().map(|x| &x.id[..]).collect();
+    assert_eq!(vec!["unique", "ambiguous", "broken", "unsolvable", "rules"], ids);
+
+    assert_eq!(Outcome::Solved, records[0].outcome);
+    assert_eq!(Some(true), records[0].unique);
+    assert_eq!((7, 7), (records[0].height, records[0].width));
+
+    assert_eq!(Some(false), records[1].unique);
+    assert_eq!(2, records[1].solver_calls);
+    assert!(records[1].variables > 0 && records[1].clauses > 0);
+
+    assert_eq!(Outcome::Invalid, records[2].outcome);
+    assert_eq!(Some("Unexpected square".to_string()), records[2].error);
+    assert_eq!((0, 0), (records[2].height, records[2].width));
+
+    assert_eq!(Outcome::Unsolved, records[3].outcome);
+    assert_eq!(None, records[3].unique);
+    assert!(records[3].error.is_some());
+
+    assert!(records[4].solved_by_rules);
+    assert_eq!(0, records[4].solver_calls);
+}
+
+#[test]
+fn test_solve_batch_thread_counts() {
+    let puzzles: Vec<_> = (0..10).map(|x| puzzle(&x.to_string(), "____3__X_", 3, 3)).collect();
+    for &threads in [0, 1, 4, 20].iter() {
+        let records = solve_batch(&puzzles, solve, 1, threads);
+        assert_eq!(10, records.len());
+        assert!(records.iter().enumerate().all(|(idx, x)| x.id == idx.to_string()));
+    }
+    assert!(solve_batch(&[], solve, 0, 4).is_empty());
+}
+
+#[test]
+fn test_to_csv() {
+    let records = solve_batch(&[puzzle("a.grid", "____3__X_", 3, 3)], solve, 0, 1);
+    let fields: Vec<String> = records[0].to_csv().split(',').map(|x| x.to_string()).collect();
+    assert_eq!(CSV_HEADER.split(',').count(), fields.len());
+    assert_eq!(vec!["a.grid", "3", "3", "solved", "true", "true", "0", "0", "0"],
+               fields[..9].to_vec());
+    assert_eq!("", fields[10]);
+
+    let broken = BatchPuzzle { id: "x,\"y\"".to_string(), puzzle: Err("line 1, column 2".to_string()) };
+    assert_eq!("\"x,\"\"y\"\"\",0,0,invalid,,false,0,0,0,0,\"line 1, column 2\"",
+               solve_batch(&[broken], solve, 0, 1)[0].to_csv());
+}
@@ -0,0 +1,168 @@
+use super::{GridData, SatSolver};
+use super::grade::{grade_puzzle, Difficulty};
+use super::solve_puzzle;
+use super::svg::{render_fragment, svg_size, SvgOptions};
+
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+/// The height of the caption above each puzzle, in pixels.
+const CAPTION_HEIGHT: u32 = 24;
+/// The space at the top of each page for its heading, in pixels.
+const HEADING_HEIGHT: u32 = 40;
+/// The space at the bottom of each page for its number, in pixels.
+const FOOTER_HEIGHT: u32 = 24;
+const MIN_CELL_SIZE: u32 = 4;
+const MAX_CELL_SIZE: u32 = 40;
+
+/// A puzzle along with everything the booklet prints about it.
+#[derive(Clone)]
+pub struct BookletPuzzle {
+    pub title: String,
+    pub puzzle: GridData,
+    pub solution: GridData,
+    pub difficulty: Difficulty,
+}
+
+impl BookletPuzzle {
+    /// Grades and solves the given puzzle for the booklet.
+    pub fn new(title: &str, puzzle: GridData, solver: SatSolver) -> Result<BookletPuzzle, String> {
+        let difficulty = grade_puzzle(&puzzle)
+            .map_err(|e| format!("{}: {}", title, e))?.difficulty;
+        let mut solution = puzzle.clone();
+        solve_puzzle(&mut solution, solver).map_err(|e| format!("{}: {}", title, e))?;
+        Ok(BookletPuzzle { title: title.to_string(), puzzle, solution, difficulty })
+    }
+}
+
+/// How the pages of a booklet are laid out. Sizes are in pixels.
+#[derive(Clone, Debug)]
+pub struct BookletOptions {
+    pub title: String,
+    pub page_width: u32,
+    pub page_height: u32,
+    pub margin: u32,
+    /// The number of puzzles side by side on a page.
+    pub columns: u32,
+    /// The number of puzzles one above the other on a page.
+    pub rows: u32,
+}
+
+impl Default for BookletOptions {
+    /// A4 pages at 96 pixels per inch, with six puzzles to a page.
+    fn default() -> BookletOptions {
+        BookletOptions {
+            title: "Light Up".to_string(),
+            page_width: 794,
+            page_height: 1123,
+            margin: 48,
+            columns: 2,
+            rows: 3,
+        }
+    }
+}
+
+/**
+ * Lays out the puzzles as the pages of a booklet, each page a standalone SVG
+ * document. The puzzles come first, numbered and captioned with their titles
+ * and difficulties, followed by an answer key that shows the solutions in the
+ * same order and layout.
+ */
+pub fn render_booklet(puzzles: &[BookletPuzzle], options: &BookletOptions) -> Vec<String> {
+    let per_page = (options.columns * options.rows).max(1) as usize;
+    let puzzle_pages = puzzles.len().div_ceil(per_page);
+    let total_pages = 2 * puzzle_pages;
+
+    let mut pages = Vec::with_capacity(total_pages);
+    for show_solution in [false, true].iter().cloned() {
+        for (page_idx, chunk) in puzzles.chunks(per_page).enumerate() {
+            let heading = if show_solution {
+                format!("{} \u{2014} Answers", options.title)
+            } else {
+                options.title.clone()
+            };
+            let first_number = page_idx * per_page + 1;
+            let page_number = pages.len() + 1;
+            pages.push(render_page(chunk, first_number, show_solution, &heading,
+                                   page_number, total_pages, options));
+        }
+    }
+    pages
+}
+
+fn render_page(puzzles: &[BookletPuzzle], first_number: usize, show_solution: bool,
+               heading: &str, page_number: usize, total_pages: usize,
+               options: &BookletOptions) -> String {
+    let mut out = String::new();
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+                   viewBox=\"0 0 {0} {1}\">", options.page_width, options.page_height).unwrap();
+    writeln!(out, "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+             options.page_width, options.page_height).unwrap();
+    writeln!(out, "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"24\" \
+                   font-weight=\"bold\">{}</text>",
+             options.margin, options.margin + 24, escape_xml(heading)).unwrap();
+
+    let (slot_width, slot_height) = slot_size(options);
+    let top = options.margin + HEADING_HEIGHT;
+    for (idx, puzzle) in puzzles.iter().enumerate() {
+        let slot_x = options.margin + (idx as u32 % options.columns) * slot_width;
+        let slot_y = top + (idx as u32 / options.columns) * slot_height;
+        let caption = format!("{}. {} ({})", first_number + idx, puzzle.title, puzzle.difficulty);
+        writeln!(out, "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"16\">{}</text>",
+                 slot_x + options.margin / 4, slot_y + 16, escape_xml(&caption)).unwrap();
+
+        let grid = if show_solution { &puzzle.solution } else { &puzzle.puzzle };
+        let svg_options = SvgOptions {
+            show_solution,
+            cell_size: cell_size_for(grid, slot_width, slot_height - CAPTION_HEIGHT),
+            ..SvgOptions::default()
+        };
+        let (width, _) = svg_size(grid, &svg_options);
+        // Center each puzzle horizontally in its slot.
+        let x = slot_x + slot_width.saturating_sub(width) / 2;
+        writeln!(out, "<g transform=\"translate({},{})\">", x, slot_y + CAPTION_HEIGHT).unwrap();
+        out.push_str(&render_fragment(grid, &svg_options));
+        out.push_str("</g>\n");
+    }
+
+    writeln!(out, "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"12\" \
+                   text-anchor=\"middle\">Page {} of {}</text>",
+             options.page_width / 2, options.page_height - options.margin / 2,
+             page_number, total_pages).unwrap();
+    out.push_str("</svg>\n");
+    out
+}
+
+/// The space each puzzle and its caption get on a page.
+fn slot_size(options: &BookletOptions) -> (u32, u32) {
+    let usable_width = options.page_width.saturating_sub(2 * options.margin);
+    let usable_height = options.page_height
+        .saturating_sub(2 * options.margin + HEADING_HEIGHT + FOOTER_HEIGHT);
+    (usable_width / options.columns.max(1), usable_height / options.rows.max(1))
+}
+
+/**
+ * The largest square size at which the drawing of the grid, including the
+ * half square border around it, fits in the given space.
+ */
+fn cell_size_for(grid: &GridData, width: u32, height: u32) -> u32 {
+    let fit_width = width / (grid.grid.width as u32 + 1);
+    let fit_height = height / (grid.grid.height as u32 + 1);
+    fit_width.min(fit_height).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
@@ -0,0 +1,81 @@
+use super::*;
+use super::super::IS_LIGHT;
+use super::super::dpll::solve;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+fn example(title: &str) -> BookletPuzzle {
+    let grid = precompute_data(get_grid_from_string("____3__X_", 3, 3).unwrap());
+    BookletPuzzle::new(title, grid, solve).unwrap()
+}
+
+#[test]
+fn test_booklet_puzzle() {
+    let puzzle = example("First");
+    assert_eq!("First", puzzle.title);
+    assert_eq!(Difficulty::Easy, puzzle.difficulty);
+    assert!(!puzzle.puzzle.grid.contents.iter().any(|&x| x & IS_LIGHT != 0));
+    assert_eq!(3, puzzle.solution.grid.contents.iter()
+               .filter(|&&x| x & IS_LIGHT != 0).count());
+
+    let unsolvable = precompute_data(get_grid_from_string("4", 1, 1).unwrap());
+    assert!(BookletPuzzle::new("Broken", unsolvable, solve).err().unwrap().starts_with("Broken: "));
+}
+
+#[test]
+fn test_render_booklet_pages() {
+    let puzzles: Vec<_> = (1..8).map(|x| example(&format!("Puzzle {}", x))).collect();
+    let pages = render_booklet(&puzzles, &BookletOptions::default());
+    assert_eq!(4, pages.len());
+    for page in pages.iter() {
+        assert!(page.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"794\" \
+                                  height=\"1123\""));
+        assert!(page.ends_with("</svg>\n"));
+    }
+    assert_eq!(6, pages[0].matches("<g transform").count());
+    assert_eq!(1, pages[1].matches("<g transform").count());
+    assert!(pages[0].contains(">1. Puzzle 1 (easy)</text>"));
+    assert!(pages[1].contains(">7. Puzzle 7 (easy)</text>"));
+    assert!(pages[1].contains(">Page 2 of 4</text>"));
+
+    assert!(pages[2].contains(">Light Up \u{2014} Answers</text>"));
+    assert!(pages[3].contains(">7. Puzzle 7 (easy)</text>"));
+    assert!(!pages[0].contains("<circle"));
+    assert_eq!(18, pages[2].matches("<circle").count());
+}
+
+#[test]
+fn test_render_booklet_layout() {
+    let options = BookletOptions {
+        title: "Club <night> & co".to_string(),
+        page_width: 400,
+        page_height: 300,
+        margin: 20,
+        columns: 2,
+        rows: 1,
+    };
+    let pages = render_booklet(&[example("A"), example("B")], &options);
+    assert_eq!(2, pages.len());
+    assert!(pages[0].contains(">Club &lt;night&gt; &amp; co</text>"));
+    // Slots are 180 by 196 pixels, so squares are 45 pixels, capped at 40.
+    assert!(pages[0].contains("<g transform=\"translate(30,84)\">"));
+    assert!(pages[0].contains("<g transform=\"translate(210,84)\">"));
+}
+
+#[test]
+fn test_render_empty_booklet() {
+    assert!(render_booklet(&[], &BookletOptions::default()).is_empty());
+}
+
+#[test]
+fn test_cell_size_for() {
+    let grid = precompute_data(get_grid_from_string(&"_".repeat(100), 10, 10).unwrap());
+    assert_eq!(20, cell_size_for(&grid, 220, 400));
+    assert_eq!(10, cell_size_for(&grid, 400, 110));
+    assert_eq!(MAX_CELL_SIZE, cell_size_for(&grid, 4000, 4000));
+    assert_eq!(MIN_CELL_SIZE, cell_size_for(&grid, 10, 10));
+}
+
+#[test]
+fn test_escape_xml() {
+    assert_eq!("a &amp; &quot;b&quot; &lt;c&gt;", escape_xml("a & \"b\" <c>"));
+}
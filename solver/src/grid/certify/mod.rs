@@ -0,0 +1,115 @@
+use super::{GridData, SatSolver, IS_LIT, CANT_LIGHT, try_solve};
+use super::cnf_format::{make_cnf_formula, CnfFormula};
+use super::coords::Coord;
+use super::rules::{self, Deduction, Mark};
+use super::utils::{insert_light, print_griddata_to_string};
+
+use serde::{Serialize, Deserialize};
+
+#[cfg(test)]
+mod tests;
+
+/// A deduction the SAT model does not agree with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsoundDeduction {
+    /// The index of the deduction in the order the rules made them.
+    pub index: usize,
+    pub deduction: Deduction,
+    /// The square whose new state is not forced by the puzzle.
+    pub square: Coord,
+    /// The pretty-printed board just before the deduction was made.
+    pub board: String,
+}
+
+/// The outcome of checking a list of deductions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Certificate {
+    /// The number of changes to the grid found to be forced by the puzzle.
+    pub checked: usize,
+    /// The first deduction that is not forced, if there is one.
+    pub unsound: Option<UnsoundDeduction>,
+}
+
+/**
+ * Applies the rules to a copy of the given puzzle with the given depth of
+ * lookahead, and checks every deduction they make with certify_deductions.
+ */
+pub fn certify_rules(grid: &GridData, solver: SatSolver, lookahead_depth: usize)
+        -> Result<Certificate, String> {
+    let mut working = grid.clone();
+    let trace = rules::populate_with_lookahead_traced(&mut working, lookahead_depth);
+    certify_deductions(grid, &trace.deductions, solver)
+}
+
+/**
+ * Checks that each change made by the given deductions holds in every
+ * solution of the original puzzle, by asking the solver whether the puzzle
+ * is satisfiable with that change negated. Only the original grid goes into
+ * the formula, so a wrong deduction can't hide behind an earlier one.
+ * Checking stops at the first change the puzzle does not force. Since the
+ * solver reports unsatisfiability as an error, the original puzzle is solved
+ * first, and an error is returned if that fails, if the solver fails on any
+ * of the checks, or if a deduction changes a square outside the grid.
+ */
+pub fn certify_deductions(grid: &GridData, deductions: &[Deduction], solver: SatSolver)
+        -> Result<Certificate, String> {
+    let formula = make_cnf_formula(grid);
+    solver(&formula).map_err(|e| format!("Could not solve the puzzle: {}", e))?;
+    let mut checked = 0;
+    for (index, deduction) in deductions.iter().enumerate() {
+        for &(square, mark) in deduction.changes.iter() {
+            let loc = grid.grid.index_of(square)
+                .ok_or_else(|| format!("Deduction {} changes square {}, which is outside the grid",
+                                       index, square))?;
+            if !is_forced(grid, &formula, loc, mark, solver)? {
+                let unsound = UnsoundDeduction {
+                    index,
+                    deduction: deduction.clone(),
+                    square,
+                    board: board_before(grid, &deductions[..index]),
+                };
+                return Ok(Certificate { checked, unsound: Some(unsound) });
+            }
+            checked += 1;
+        }
+    }
+    Ok(Certificate { checked, unsound: None })
+}
+
+/// Whether the puzzle becomes unsatisfiable once the given mark is negated.
+fn is_forced(grid: &GridData, formula: &CnfFormula, square: usize, mark: Mark,
+             solver: SatSolver) -> Result<bool, String> {
+    let negated = match mark {
+        Mark::Light => formula.variable_for(square).map(|x| vec![vec![-x]]),
+        Mark::CantLight => formula.variable_for(square).map(|x| vec![vec![x]]),
+        Mark::Lit => grid.sight_lines.get(&square).map(|sl| {
+            sl.iter().chain(Some(&square))
+                .filter_map(|&x| formula.variable_for(x))
+                .map(|x| vec![-x])
+                .collect()
+        }),
+    };
+    match negated {
+        Some(clauses) => Ok(try_solve(solver, &formula.append_clauses(clauses))?.is_none()),
+        None => Ok(false),
+    }
+}
+
+/// Replays the given deductions onto a copy of the original grid.
+fn board_before(grid: &GridData, deductions: &[Deduction]) -> String {
+    let mut board = grid.clone();
+    for deduction in deductions.iter() {
+        for &(square, mark) in deduction.changes.iter() {
+            let square = match board.grid.index_of(square) {
+                Some(x) => x,
+                None => { continue; }
+            };
+            match mark {
+                Mark::Light => { insert_light(&mut board, square); },
+                Mark::Lit => { board.grid.contents[square] |= IS_LIT; },
+                Mark::CantLight => { board.grid.contents[square] |= CANT_LIGHT; },
+            }
+        }
+    }
+    print_griddata_to_string(&board, true)
+}
@@ -0,0 +1,79 @@
+use super::*;
+use super::super::dpll::solve;
+use super::super::rules::Rule;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEST_GRID_STR: &str =
+"__1_1__
+ _______
+ X_____2
+ _______
+ 0_____0
+ _______
+ __1_0__";
+
+#[test]
+fn test_certify_rules() {
+    let grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 7, 7).unwrap());
+    for depth in 0..2 {
+        let certificate = certify_rules(&grid, solve, depth).unwrap();
+        assert!(certificate.checked > 0);
+        assert!(certificate.unsound.is_none());
+    }
+}
+
+#[test]
+fn test_certify_ambiguous_puzzle() {
+    let test_grid_str =
+        "____0
+         X1__X
+         XX__X
+         1__21
+         _X___";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    assert!(certify_rules(&grid, solve, 0).unwrap().unsound.is_none());
+}
+
+#[test]
+fn test_unsound_deduction() {
+    let grid = precompute_data(get_grid_from_string(
+        "___
+         _3_
+         _X_", 3, 3).unwrap());
+    let deductions = vec![
+        Deduction { rule: Rule::ClueFilled, loc: Coord::new(1, 1),
+                    changes: vec![(Coord::new(0, 1), Mark::Light)], premises: vec![] },
+        Deduction { rule: Rule::LoneSquare, loc: Coord::new(0, 0),
+                    changes: vec![(Coord::new(0, 0), Mark::Light)], premises: vec![] },
+    ];
+    let certificate = certify_deductions(&grid, &deductions, solve).unwrap();
+    assert_eq!(1, certificate.checked);
+    let unsound = certificate.unsound.unwrap();
+    assert_eq!(1, unsound.index);
+    assert_eq!(Coord::new(0, 0), unsound.square);
+    assert_eq!(Rule::LoneSquare, unsound.deduction.rule);
+    assert_eq!("#*#_3__X_", unsound.board.replace(char::is_whitespace, ""));
+}
+
+#[test]
+fn test_certify_unsolvable() {
+    let grid = precompute_data(get_grid_from_string("_0_", 1, 3).unwrap());
+    assert!(certify_rules(&grid, solve, 0).is_err());
+}
+
+#[test]
+fn test_certify_solver_failure() {
+    // Solves the puzzle itself, and then fails on every check.
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn fails_after_first(formula: &CnfFormula) -> Result<Vec<i32>, String> {
+        if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+            solve(formula)
+        } else {
+            Err("Error executing SAT solver".to_string())
+        }
+    }
+    let grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 7, 7).unwrap());
+    assert_eq!("Error executing SAT solver", certify_rules(&grid, fails_after_first, 0).unwrap_err());
+}
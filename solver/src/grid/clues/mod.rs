@@ -0,0 +1,263 @@
+use super::{IS_SOLID, IS_CONSTRAINED, INVALID_POSITION};
+use super::{Grid, GridData, SatSolver, try_solve};
+use super::{solve_puzzle, solve_puzzle_with_stats};
+use super::cnf_format::make_cnf_formula;
+use super::coords::Coord;
+use super::utils::{get_neighbors, precompute_data};
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+/// The most solutions listed before ranking the clues.
+const MAX_SOLUTIONS: usize = 256;
+
+/// A clue that could be added to a wall.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClueSuggestion {
+    pub loc: Coord,
+    /// The clue the wall would get, counting the lights of the first solution.
+    pub clue: u8,
+    /// How many of the other solutions the clue rules out.
+    pub eliminates: usize,
+}
+
+/// Clues that would make the solution of an ambiguous puzzle unique.
+#[derive(Clone, Debug)]
+pub struct Suggestions {
+    /// The lights of the solution the clues keep, in row-major order.
+    pub solution: Vec<Coord>,
+    /// The number of other solutions found.
+    pub alternatives: usize,
+    /// Whether those are all the other solutions there are.
+    pub all_alternatives: bool,
+    /// Every clue that rules out one of the other solutions, best first.
+    pub candidates: Vec<ClueSuggestion>,
+    /// A set of clues that together make the solution unique, none of which
+    /// can be left out.
+    pub clues: Vec<ClueSuggestion>,
+}
+
+/**
+ * Lists the clues that could each be taken away on its own without the
+ * solution stopping being unique. Taking two of them away together may
+ * still leave more than one solution. Only the walls and clues of the grid
+ * are looked at. Returns an error if the solution isn't unique to begin
+ * with.
+ */
+pub fn redundant_clues(grid: &Grid, solver: SatSolver) -> Result<Vec<Coord>, String> {
+    let mut puzzle = walls_and_clues(grid);
+    if !is_unique(&puzzle, solver)? {
+        return Err("Puzzle does not have a unique solution".to_string());
+    }
+    let mut redundant = Vec::new();
+    for loc in clues(&puzzle) {
+        let clue = puzzle.contents[loc];
+        puzzle.contents[loc] = IS_SOLID;
+        if is_unique(&puzzle, solver)? {
+            redundant.push(puzzle.coord_of(loc));
+        }
+        puzzle.contents[loc] = clue;
+    }
+    Ok(redundant)
+}
+
+/**
+ * Takes clues away from the puzzle, in row-major order, for as long as the
+ * solution stays unique, so that every clue left is needed. One pass is
+ * enough, since a clue that was needed stays needed when others are taken
+ * away. Returns an error if the solution isn't unique to begin with.
+ */
+pub fn minimize_clues(grid: &Grid, solver: SatSolver) -> Result<Grid, String> {
+    let mut puzzle = walls_and_clues(grid);
+    if !is_unique(&puzzle, solver)? {
+        return Err("Puzzle does not have a unique solution".to_string());
+    }
+    for loc in clues(&puzzle) {
+        let clue = puzzle.contents[loc];
+        puzzle.contents[loc] = IS_SOLID;
+        if !is_unique(&puzzle, solver)? {
+            puzzle.contents[loc] = clue;
+        }
+    }
+    Ok(puzzle)
+}
+
+/**
+ * Ranks the clues that could be added to the walls of the grid by how many
+ * of the alternative solutions they rule out, given as lists of lights,
+ * best first, and then in row-major order. Each clue counts the lights of
+ * the reference solution, so it never rules that one out. Walls that
+ * already have a clue, and clues that rule nothing out, are left out, as
+ * are lights outside the grid.
+ */
+pub fn rank_clues(grid: &GridData, reference: &[Coord], alternatives: &[Vec<Coord>]) -> Vec<ClueSuggestion> {
+    let size = grid.grid.contents.len();
+    let as_mask = |lights: &[Coord]| {
+        let mut mask = vec![false; size];
+        for loc in lights.iter().filter_map(|&x| grid.grid.index_of(x)) {
+            mask[loc] = true;
+        }
+        mask
+    };
+    let reference = as_mask(reference);
+    let alternatives: Vec<Vec<bool>> = alternatives.iter().map(|x| as_mask(x)).collect();
+
+    let mut ranked = Vec::new();
+    for loc in 0..size {
+        let square = grid.grid.contents[loc];
+        if square & IS_SOLID == 0 || square & IS_CONSTRAINED != 0 {
+            continue;
+        }
+        let neighbors = empty_neighbors(grid, loc);
+        if neighbors.is_empty() {
+            continue;
+        }
+        let count = |mask: &Vec<bool>| neighbors.iter().filter(|&&x| mask[x]).count();
+        let clue = count(&reference);
+        let eliminates = alternatives.iter().filter(|x| count(x) != clue).count();
+        if eliminates > 0 {
+            ranked.push(ClueSuggestion { loc: grid.grid.coord_of(loc), clue: clue as u8, eliminates });
+        }
+    }
+    ranked.sort_by_key(|x| (Reverse(x.eliminates), x.loc));
+    ranked
+}
+
+/**
+ * Finds clues to add to the walls of an ambiguous puzzle so that its
+ * solution becomes unique. Up to a few hundred of its solutions are listed,
+ * and the first is kept. Clues are picked greedily, each time the one that
+ * rules out the most of the others that are left, until all of them are
+ * ruled out. If the puzzle still has another solution, it is added to the
+ * list and the clues are picked again. Finally, clues are left out again
+ * while the solution stays unique. Only the walls and clues of the grid
+ * are looked at. Returns an error if the puzzle has no solution, or if no
+ * clues on its walls can tell two of its solutions apart.
+ */
+pub fn suggest_clues(grid: &Grid, solver: SatSolver) -> Result<Suggestions, String> {
+    let puzzle = walls_and_clues(grid);
+    let data = precompute_data(puzzle.clone());
+    let mut solutions = list_solutions(&data, MAX_SOLUTIONS, solver)?;
+    if solutions.is_empty() {
+        return Err("Puzzle has no solution".to_string());
+    }
+    let all_alternatives = solutions.len() < MAX_SOLUTIONS;
+    let solution = solutions.remove(0);
+    let mut alternatives = solutions;
+
+    let mut chosen: Vec<ClueSuggestion>;
+    loop {
+        chosen = Vec::new();
+        let mut left = alternatives.clone();
+        while !left.is_empty() {
+            let best = match rank_clues(&data, &solution, &left).into_iter().next() {
+                Some(x) => x,
+                None => { return Err("No clue on the walls tells the solutions apart".to_string()); }
+            };
+            left.retain(|x| count_lights(&data, best.loc, x) == best.clue as usize);
+            chosen.push(best);
+        }
+        match other_solution(&with_clues(&puzzle, &chosen), &solution, solver)? {
+            Some(other) => { alternatives.push(other); },
+            None => { break; }
+        }
+    }
+
+    // Leave out the clues picked first last, since they rule out the most.
+    for idx in (0..chosen.len()).rev() {
+        let mut fewer = chosen.clone();
+        fewer.remove(idx);
+        if is_unique(&with_clues(&puzzle, &fewer), solver)? {
+            chosen = fewer;
+        }
+    }
+
+    let candidates = rank_clues(&data, &solution, &alternatives);
+    for clue in chosen.iter_mut() {
+        clue.eliminates = candidates.iter().find(|x| x.loc == clue.loc).map_or(0, |x| x.eliminates);
+    }
+    Ok(Suggestions {
+        solution,
+        alternatives: alternatives.len(),
+        all_alternatives,
+        candidates,
+        clues: chosen,
+    })
+}
+
+/**
+ * Lists up to the given number of solutions, as their lights, by ruling out
+ * each one found until the formula is unsatisfiable. Returns an error if the
+ * solver fails.
+ */
+fn list_solutions(grid: &GridData, limit: usize, solver: SatSolver) -> Result<Vec<Vec<Coord>>, String> {
+    let mut formula = make_cnf_formula(grid);
+    let locations: HashMap<i32, Coord> = (0..grid.grid.contents.len())
+        .filter_map(|x| formula.variable_for(x).map(|var| (var, grid.grid.coord_of(x))))
+        .collect();
+    let mut solutions = Vec::new();
+    while solutions.len() < limit {
+        let result = match try_solve(solver, &formula)? {
+            Some(x) => x,
+            None => { break; }
+        };
+        let mut lights: Vec<Coord> = result.iter().filter_map(|x| locations.get(x).cloned()).collect();
+        lights.sort();
+        solutions.push(lights);
+        formula = formula.append_inverse(&result);
+    }
+    Ok(solutions)
+}
+
+/// A solution of the puzzle other than the given one, if it has one.
+fn other_solution(puzzle: &Grid, solution: &[Coord], solver: SatSolver) -> Result<Option<Vec<Coord>>, String> {
+    let found = solve_puzzle_with_stats(&mut precompute_data(puzzle.clone()), solver, 0)?;
+    let found = Some(found.lights).into_iter().chain(found.second_solution);
+    for mut lights in found {
+        lights.sort();
+        if lights != solution {
+            return Ok(Some(lights));
+        }
+    }
+    Ok(None)
+}
+
+fn empty_neighbors(grid: &GridData, loc: usize) -> Vec<usize> {
+    get_neighbors(grid, loc).1[..4].iter().cloned()
+        .filter(|&x| x != INVALID_POSITION && grid.grid.contents[x] & IS_SOLID == 0)
+        .collect()
+}
+
+/// The number of the sorted lights next to the square.
+fn count_lights(grid: &GridData, square: Coord, lights: &[Coord]) -> usize {
+    let neighbors = grid.grid.index_of(square).map_or(Vec::new(), |loc| empty_neighbors(grid, loc));
+    neighbors.iter().filter(|&&x| lights.binary_search(&grid.grid.coord_of(x)).is_ok()).count()
+}
+
+fn with_clues(puzzle: &Grid, clues: &[ClueSuggestion]) -> Grid {
+    let mut puzzle = puzzle.clone();
+    for clue in clues {
+        if let Some(loc) = puzzle.index_of(clue.loc) {
+            puzzle.contents[loc] = IS_SOLID | IS_CONSTRAINED | clue.clue;
+        }
+    }
+    puzzle
+}
+
+/// Whether the puzzle has exactly one solution.
+pub fn is_unique(puzzle: &Grid, solver: SatSolver) -> Result<bool, String> {
+    solve_puzzle(&mut precompute_data(puzzle.clone()), solver).map(|x| x.1)
+}
+
+fn clues(puzzle: &Grid) -> Vec<usize> {
+    (0..puzzle.contents.len()).filter(|&x| puzzle.contents[x] & IS_CONSTRAINED != 0).collect()
+}
+
+/// The grid without any lights or marks.
+pub(crate) fn walls_and_clues(grid: &Grid) -> Grid {
+    let contents = grid.contents.iter().map(|&x| if x & IS_SOLID != 0 { x } else { 0 }).collect();
+    Grid { contents, ..grid.clone() }
+}
@@ -0,0 +1,108 @@
+use super::*;
+use super::super::cnf_format::CnfFormula;
+use super::super::dpll::solve;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+fn coords(squares: &[(usize, usize)]) -> Vec<Coord> {
+    squares.iter().map(|&x| Coord::from(x)).collect()
+}
+
+#[test]
+fn test_redundant_clues() {
+    // The 3 alone forces every light, and so do the other clues together.
+    let grid = get_grid_from_string("2_2_3_1X_", 3, 3).unwrap();
+    assert_eq!(coords(&[(0, 0), (0, 2), (1, 1), (2, 0)]), redundant_clues(&grid, solve).unwrap());
+
+    let grid = get_grid_from_string("X_X_3_XX_", 3, 3).unwrap();
+    assert!(redundant_clues(&grid, solve).unwrap().is_empty());
+}
+
+#[test]
+fn test_minimize_clues() {
+    let grid = get_grid_from_string("2_2_3_1X_", 3, 3).unwrap();
+    let minimal = minimize_clues(&grid, solve).unwrap();
+    assert_eq!("X_X_3_XX_", print_grid_to_string(&minimal, false));
+    assert!(redundant_clues(&minimal, solve).unwrap().is_empty());
+}
+
+#[test]
+fn test_lights_are_ignored() {
+    let grid = get_grid_from_string("2*2*3*1X#", 3, 3).unwrap();
+    assert_eq!("X_X_3_XX_", print_grid_to_string(&minimize_clues(&grid, solve).unwrap(), false));
+}
+
+#[test]
+fn test_ambiguous_puzzles() {
+    let grid = get_grid_from_string("____", 2, 2).unwrap();
+    assert!(redundant_clues(&grid, solve).is_err());
+    assert!(minimize_clues(&grid, solve).is_err());
+    assert!(!is_unique(&grid, solve).unwrap());
+}
+
+#[test]
+fn test_rank_clues() {
+    // Lighting the middle, or one end of each arm of the cross, solves it.
+    let grid = precompute_data(get_grid_from_string("X_X___X_X", 3, 3).unwrap());
+    let alternatives = [coords(&[(0, 1), (1, 0)]), coords(&[(0, 1), (1, 2)]),
+                        coords(&[(1, 0), (2, 1)]), coords(&[(1, 2), (2, 1)])];
+    let middle = coords(&[(1, 1)]);
+    let ranked = rank_clues(&grid, &middle, &alternatives);
+    let expected: Vec<ClueSuggestion> = coords(&[(0, 0), (0, 2), (2, 0), (2, 2)]).into_iter()
+        .map(|loc| ClueSuggestion { loc, clue: 0, eliminates: 3 })
+        .collect();
+    assert_eq!(expected, ranked);
+
+    let ranked = rank_clues(&grid, &alternatives[0], &alternatives[1..]);
+    assert_eq!(ClueSuggestion { loc: Coord::new(0, 0), clue: 2, eliminates: 3 }, ranked[0]);
+    assert!(rank_clues(&grid, &middle, &[]).is_empty());
+}
+
+#[test]
+fn test_suggest_clues() {
+    let grid = get_grid_from_string("X_X___X_X", 3, 3).unwrap();
+    let suggestions = suggest_clues(&grid, solve).unwrap();
+    assert_eq!(4, suggestions.alternatives);
+    assert!(suggestions.all_alternatives);
+    assert!(suggestions.candidates.windows(2).all(|x| x[0].eliminates >= x[1].eliminates));
+    assert!(!suggestions.clues.is_empty());
+
+    let mut puzzle = grid.clone();
+    for clue in suggestions.clues.iter() {
+        let loc = puzzle.index_of(clue.loc).unwrap();
+        puzzle.contents[loc] = IS_SOLID | IS_CONSTRAINED | clue.clue;
+    }
+    assert!(is_unique(&puzzle, solve).unwrap());
+    for clue in suggestions.clues.iter() {
+        let mut fewer = puzzle.clone();
+        let loc = puzzle.index_of(clue.loc).unwrap();
+        fewer.contents[loc] = IS_SOLID;
+        assert!(!is_unique(&fewer, solve).unwrap());
+    }
+}
+
+#[test]
+fn test_suggest_clues_for_unique_puzzle() {
+    let grid = get_grid_from_string("X_X_3_XX_", 3, 3).unwrap();
+    let suggestions = suggest_clues(&grid, solve).unwrap();
+    assert_eq!(coords(&[(0, 1), (1, 0), (1, 2)]), suggestions.solution);
+    assert_eq!(0, suggestions.alternatives);
+    assert!(suggestions.clues.is_empty());
+}
+
+#[test]
+fn test_suggest_clues_errors() {
+    // Without walls, there is nowhere to put a clue.
+    let grid = get_grid_from_string("____", 2, 2).unwrap();
+    assert_eq!("No clue on the walls tells the solutions apart", suggest_clues(&grid, solve).err().unwrap());
+    let grid = get_grid_from_string("_0_", 1, 3).unwrap();
+    assert_eq!("Puzzle has no solution", suggest_clues(&grid, solve).err().unwrap());
+}
+
+#[test]
+fn test_suggest_clues_solver_failure() {
+    fn broken(_: &CnfFormula) -> Result<Vec<i32>, String> {
+        Err("Error executing SAT solver".to_string())
+    }
+    let grid = get_grid_from_string("X_X___X_X", 3, 3).unwrap();
+    assert_eq!("Error executing SAT solver", suggest_clues(&grid, broken).err().unwrap());
+}
@@ -23,49 +23,64 @@ pub struct CnfFormula {
 
 impl CnfFormula {
     pub fn write_to_file<T>(&self, file: &mut T) -> Result<()> where T: Write {
-        try!(write!(file, "p cnf {} {}\n",
-                    self.grid_to_cnf_position_mapping.len(),
-                    self.clauses.len()));
+        writeln!(file, "p cnf {} {}",
+                 self.grid_to_cnf_position_mapping.len(),
+                 self.clauses.len())?;
         for clause in self.clauses.iter() {
             for &num in clause.iter() {
-                try!(write!(file, "{} ", num));
+                write!(file, "{} ", num)?;
             }
-            try!(write!(file, "0\n"));
+            writeln!(file, "0")?;
         }
         Ok(())
     }
 
-    pub fn append_inverse(&self, result: &Vec<i32>) -> CnfFormula {
-        let clause_to_insert = result.iter().map(|&x| -x).collect();
+    pub fn append_inverse(&self, result: &[i32]) -> CnfFormula {
+        self.append_clause(result.iter().map(|&x| -x).collect())
+    }
+
+    pub fn append_clause(&self, clause: Vec<i32>) -> CnfFormula {
+        self.append_clauses(vec![clause])
+    }
+
+    pub fn append_clauses(&self, clauses: Vec<Vec<i32>>) -> CnfFormula {
         let mut result_clauses = self.clauses.clone();
-        result_clauses.push(clause_to_insert);
+        result_clauses.extend(clauses);
         CnfFormula {
             grid_to_cnf_position_mapping: self.grid_to_cnf_position_mapping.clone(),
             cnf_to_grid_position_mapping: self.cnf_to_grid_position_mapping.clone(),
             clauses: result_clauses
         }
     }
+
+    /// The CNF variable standing for a light at the given grid position, if there is one.
+    pub fn variable_for(&self, loc: usize) -> Option<i32> {
+        self.grid_to_cnf_position_mapping.get(&loc).cloned()
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.grid_to_cnf_position_mapping.len()
+    }
+
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
 }
 
 struct ConstraintCnfGenerator {
-    total_size: u32,
     cnf_clauses: Vec<Vec<(u32, Vec<bool>)>>
 }
 
 fn make_constraint_cnf_generator(size: u32) -> ConstraintCnfGenerator {
-    let clauses = (0..(size + 1)).map(|i| make_constraint_cnf_cache(i)).collect();
-    ConstraintCnfGenerator { total_size: size, cnf_clauses: clauses }
+    let clauses = (0..(size + 1)).map(make_constraint_cnf_cache).collect();
+    ConstraintCnfGenerator { cnf_clauses: clauses }
 }
 
 fn make_constraint_cnf_cache(size: u32) -> Vec<(u32, Vec<bool>)> { 
     let mut to_explore: VecDeque<(u32, Vec<bool>)> = VecDeque::new();
     let mut result: Vec<(u32, Vec<bool>)> = Vec::new();
     to_explore.push_front((0, Vec::new()));
-    loop {
-        let mut next = match to_explore.pop_back() {
-            Some(x) => x,
-            None => { break; }
-        };
+    while let Some(mut next) = to_explore.pop_back() {
         if next.1.len() as u32 == size {
             result.push(next);
         } else {
@@ -81,8 +96,8 @@ fn make_constraint_cnf_cache(size: u32) -> Vec<(u32, Vec<bool>)> {
 
 impl ConstraintCnfGenerator {
     fn get_constraints<'a>(&'a self, sat_ids: Vec<i32>, num_true: u32)
-            -> Box<Iterator<Item=Vec<i32>> + 'a> {
-        let ref bool_arrays = self.cnf_clauses[sat_ids.len()];
+            -> Box<dyn Iterator<Item=Vec<i32>> + 'a> {
+        let bool_arrays = &self.cnf_clauses[sat_ids.len()];
         Box::new(bool_arrays.iter()
             .filter(move |&x| x.0 != num_true)
             .map(move |x| x.1.iter().enumerate().map(|y: (usize, &bool)| {
@@ -111,7 +126,7 @@ pub fn make_cnf_formula(grid: &GridData) -> CnfFormula {
                     grid, &constraint_cnf_gen, &grid_to_cnf, grid_idx));
         } 
         if does_need_light(grid.grid.contents[grid_idx]) {
-            clauses.push(get_sight_line_clauses(&grid, &grid_to_cnf, grid_idx));
+            clauses.push(get_sight_line_clauses(grid, &grid_to_cnf, grid_idx));
         }
     }
 
@@ -123,7 +138,7 @@ pub fn make_cnf_formula(grid: &GridData) -> CnfFormula {
     CnfFormula {
         grid_to_cnf_position_mapping: grid_to_cnf,
         cnf_to_grid_position_mapping: cnf_to_grid,
-        clauses: clauses
+        clauses
     }
 }
 
@@ -167,7 +182,7 @@ fn get_cnf_ids_within_sight(grid: &GridData, cnf_to_grid: &HashMap<i32, usize>,
                             grid_to_cnf: &HashMap<usize, i32>) -> HashSet<(i32, i32)> {
     let mut cnf_ids_within_sight: HashSet<(i32, i32)> = HashSet::new();
     for (cnf_idx, grid_idx) in cnf_to_grid {
-        let ref sight_line = match grid.sight_lines.get(grid_idx) {
+        let sight_line = match grid.sight_lines.get(grid_idx) {
             Some(x) => x,
             None => { continue; }
         };
@@ -182,12 +197,11 @@ fn get_cnf_ids_within_sight(grid: &GridData, cnf_to_grid: &HashMap<i32, usize>,
 
 fn get_sight_line_clauses(grid: &GridData, to_cnf: &HashMap<usize, i32>, loc: usize) -> Vec<i32> {
     let mut result = Vec::new();
-    match to_cnf.get(&loc) {
-        Some(i) => { result.push(*i); },
-        None => { }
-    };
+    if let Some(i) = to_cnf.get(&loc) {
+        result.push(*i);
+    }
 
-    let ref sight_line = match grid.sight_lines.get(&loc) {
+    let sight_line = match grid.sight_lines.get(&loc) {
         Some(x) => x,
         None => { return result; }
     };
@@ -197,9 +211,9 @@ fn get_sight_line_clauses(grid: &GridData, to_cnf: &HashMap<usize, i32>, loc: us
 
 fn get_numerical_constraint_clauses<'a, 'b>(
     grid: &'a GridData, gen: &'b ConstraintCnfGenerator,
-    to_cnf: &'a HashMap<usize, i32>, loc: usize) -> Box<Iterator<Item=Vec<i32>> + 'b> {
+    to_cnf: &'a HashMap<usize, i32>, loc: usize) -> Box<dyn Iterator<Item=Vec<i32>> + 'b> {
     let adj_neighbors = get_neighbors(grid, loc).1[..4]
-        .into_iter()
+        .iter()
         .filter(|&&x| x != INVALID_POSITION)
         .map(usize::clone)
         .collect::<Vec<usize>>();
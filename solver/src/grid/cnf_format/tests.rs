@@ -3,18 +3,9 @@ use super::{make_constraint_cnf_cache, make_constraint_cnf_generator};
 use super::super::utils::*;
 
 use std::collections::HashSet;
-use std::collections::HashMap;
 use std::cmp::Eq;
 use std::cmp::PartialEq;
 use std::hash::Hash;
-use std::str;
-
-static TEST_GRID_STR: &'static str = "
-____X
-X1__X
-XX__X
-X__21
-_X___";
 
 #[test]
 fn test_make_cnf_cache() {
@@ -76,9 +67,9 @@ fn get_constraints_test_helper(sat_ids: Vec<i32>, num_true: u32, disallowed_sums
     let expval = 1 << sat_ids.len();
 
     let constraint_clauses: Vec<_> = gen.get_constraints(sat_ids, num_true).collect();
-    assert!(constraint_clauses.iter().fold(true, |b, v| b && v.len() == slen));
+    assert!(constraint_clauses.iter().all(|v| v.len() == slen));
     let constraint_clause_sums: Vec<_> = constraint_clauses.iter()
-        .map(|x| x.iter().fold(0, |a, y| a + y)).collect();
+        .map(|x| x.iter().sum::<i32>()).collect();
     println!("{:?}", constraint_clauses);
     for s in disallowed_sums.iter() {
         assert!(!constraint_clause_sums.contains(s));
@@ -163,8 +154,8 @@ fn test_sight_line_clause() {
     let sight_line_clause_2: HashSet<i32> =
         super::get_sight_line_clauses(&grid, &to_cnf, 15).into_iter().collect();
 
-    let expected_clause_1 = [1, 4, 5, 6].into_iter().cloned().collect::<HashSet<_>>();
-    let expected_clause_2 = [7].into_iter().cloned().collect::<HashSet<_>>();
+    let expected_clause_1 = [1, 4, 5, 6].iter().cloned().collect::<HashSet<_>>();
+    let expected_clause_2 = [7].iter().cloned().collect::<HashSet<_>>();
 
     assert_eq!(expected_clause_1, sight_line_clause_1);
     assert_eq!(expected_clause_2, sight_line_clause_2);
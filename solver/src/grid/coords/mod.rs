@@ -0,0 +1,126 @@
+use super::Grid;
+
+use serde::{Serialize, Deserialize};
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+/**
+ * The position of a square by its 0-based row and column. In JSON, it is a
+ * [row, column] pair. Squares are stored in row-major order, so the square
+ * at index loc of a grid of the given width is at row loc / width and column
+ * loc % width.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "(usize, usize)", into = "(usize, usize)")]
+pub struct Coord {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Coord {
+    pub fn new(row: usize, col: usize) -> Coord {
+        Coord { row, col }
+    }
+
+    pub fn from_index(loc: usize, width: usize) -> Coord {
+        Coord { row: loc / width, col: loc % width }
+    }
+
+    pub fn to_index(self, width: usize) -> usize {
+        self.row * width + self.col
+    }
+}
+
+impl From<(usize, usize)> for Coord {
+    fn from(pair: (usize, usize)) -> Coord {
+        Coord { row: pair.0, col: pair.1 }
+    }
+}
+
+impl From<Coord> for (usize, usize) {
+    fn from(coord: Coord) -> (usize, usize) {
+        (coord.row, coord.col)
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.row, self.col)
+    }
+}
+
+impl Grid {
+    /// The row and column of the square at the given index.
+    pub fn coord_of(&self, loc: usize) -> Coord {
+        Coord::from_index(loc, self.width as usize)
+    }
+
+    /// The index of the square at the given position, if it is in the grid.
+    pub fn index_of(&self, coord: Coord) -> Option<usize> {
+        if coord.row < self.height as usize && coord.col < self.width as usize {
+            Some(coord.to_index(self.width as usize))
+        } else {
+            None
+        }
+    }
+}
+
+/// The ways a square can be written as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordStyle {
+    /// The index of the square in row-major order, such as 7.
+    Flat,
+    /// The 0-based row and column, such as 2,1.
+    ZeroBased,
+    /// The 1-based row and column, such as 3,2, as puzzles are usually described.
+    OneBased,
+}
+
+impl FromStr for CoordStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CoordStyle, String> {
+        match s {
+            "flat" => Ok(CoordStyle::Flat),
+            "0-based" => Ok(CoordStyle::ZeroBased),
+            "1-based" => Ok(CoordStyle::OneBased),
+            _ => Err(format!("Unknown coordinate style {}", s)),
+        }
+    }
+}
+
+impl CoordStyle {
+    /// Writes the given square of the grid in this style.
+    pub fn format(self, grid: &Grid, coord: Coord) -> String {
+        match self {
+            CoordStyle::Flat => coord.to_index(grid.width as usize).to_string(),
+            CoordStyle::ZeroBased => format!("{},{}", coord.row, coord.col),
+            CoordStyle::OneBased => format!("{},{}", coord.row + 1, coord.col + 1),
+        }
+    }
+
+    /// Reads a square of the grid written in this style.
+    pub fn parse(self, grid: &Grid, text: &str) -> Result<Coord, String> {
+        let invalid = || format!("Invalid square {}", text);
+        let coord = match self {
+            CoordStyle::Flat => grid.coord_of(text.trim().parse::<usize>().map_err(|_| invalid())?),
+            CoordStyle::ZeroBased | CoordStyle::OneBased => {
+                let parts = text.split(',').map(|x| x.trim().parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+                let offset = if self == CoordStyle::OneBased { 1 } else { 0 };
+                match parts[..] {
+                    [row, col] if row >= offset && col >= offset => Coord::new(row - offset, col - offset),
+                    _ => { return Err(invalid()); }
+                }
+            },
+        };
+        match grid.index_of(coord) {
+            Some(_) => Ok(coord),
+            None => Err(format!("Square {} is outside the grid", text)),
+        }
+    }
+}
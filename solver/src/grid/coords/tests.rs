@@ -0,0 +1,62 @@
+use super::*;
+use super::super::utils::get_grid_from_string;
+
+use serde_json;
+
+#[test]
+fn test_coord_index_conversion() {
+    assert_eq!(Coord::new(2, 1), Coord::from_index(7, 3));
+    assert_eq!(7, Coord::new(2, 1).to_index(3));
+    assert_eq!(Coord::new(0, 0), Coord::from_index(0, 5));
+    assert_eq!(Coord::new(1, 2), Coord::from((1, 2)));
+    assert_eq!((1, 2), <(usize, usize)>::from(Coord::new(1, 2)));
+    assert_eq!("(1, 2)", Coord::new(1, 2).to_string());
+}
+
+#[test]
+fn test_grid_coords() {
+    let grid = get_grid_from_string("______", 2, 3).unwrap();
+    assert_eq!(Coord::new(1, 0), grid.coord_of(3));
+    assert_eq!(Some(5), grid.index_of(Coord::new(1, 2)));
+    assert_eq!(None, grid.index_of(Coord::new(2, 0)));
+    assert_eq!(None, grid.index_of(Coord::new(0, 3)));
+}
+
+#[test]
+fn test_coord_json() {
+    assert_eq!("[2,1]", serde_json::to_string(&Coord::new(2, 1)).unwrap());
+    assert_eq!(Coord::new(2, 1), serde_json::from_str::<Coord>("[2, 1]").unwrap());
+}
+
+#[test]
+fn test_format_coords() {
+    let grid = get_grid_from_string("______", 2, 3).unwrap();
+    assert_eq!("4", CoordStyle::Flat.format(&grid, Coord::new(1, 1)));
+    assert_eq!("1,1", CoordStyle::ZeroBased.format(&grid, Coord::new(1, 1)));
+    assert_eq!("2,2", CoordStyle::OneBased.format(&grid, Coord::new(1, 1)));
+}
+
+#[test]
+fn test_parse_coords() {
+    let grid = get_grid_from_string("______", 2, 3).unwrap();
+    assert_eq!(Ok(Coord::new(1, 1)), CoordStyle::Flat.parse(&grid, "4"));
+    assert_eq!(Ok(Coord::new(1, 2)), CoordStyle::ZeroBased.parse(&grid, "1,2"));
+    assert_eq!(Ok(Coord::new(1, 2)), CoordStyle::OneBased.parse(&grid, "2, 3"));
+    assert_eq!(Ok(Coord::new(0, 0)), CoordStyle::OneBased.parse(&grid, "1,1"));
+
+    assert_eq!(Err("Square 6 is outside the grid".to_string()), CoordStyle::Flat.parse(&grid, "6"));
+    assert_eq!(Err("Square 2,0 is outside the grid".to_string()),
+               CoordStyle::ZeroBased.parse(&grid, "2,0"));
+    assert_eq!(Err("Invalid square 0,1".to_string()), CoordStyle::OneBased.parse(&grid, "0,1"));
+    assert_eq!(Err("Invalid square 1".to_string()), CoordStyle::ZeroBased.parse(&grid, "1"));
+    assert_eq!(Err("Invalid square 1,2,3".to_string()), CoordStyle::ZeroBased.parse(&grid, "1,2,3"));
+    assert_eq!(Err("Invalid square a".to_string()), CoordStyle::Flat.parse(&grid, "a"));
+}
+
+#[test]
+fn test_coord_style_from_str() {
+    assert_eq!(Ok(CoordStyle::Flat), "flat".parse::<CoordStyle>());
+    assert_eq!(Ok(CoordStyle::ZeroBased), "0-based".parse::<CoordStyle>());
+    assert_eq!(Ok(CoordStyle::OneBased), "1-based".parse::<CoordStyle>());
+    assert!("2-based".parse::<CoordStyle>().is_err());
+}
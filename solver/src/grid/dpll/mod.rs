@@ -0,0 +1,188 @@
+use super::UNSAT;
+use super::cnf_format::CnfFormula;
+
+#[cfg(test)]
+mod tests;
+
+const UNASSIGNED: i8 = 0;
+
+/**
+ * A small built-in SAT solver, usable wherever a SatSolver is expected.
+ * It runs DPLL with two watched literals per clause and chronological
+ * backtracking, which is plenty for puzzles of the sizes we deal with.
+ * Like the external solver, it returns the value of every variable as a
+ * signed literal, and the UNSAT error if the formula is unsatisfiable.
+ */
+pub fn solve(cnf: &CnfFormula) -> Result<Vec<i32>, String> {
+    let num_vars = cnf.num_variables();
+    let mut state = match State::new(num_vars, cnf.clauses()) {
+        Some(x) => x,
+        None => { return Err(UNSAT.to_string()); }
+    };
+    if !state.search() {
+        return Err(UNSAT.to_string());
+    }
+    Ok((1..(num_vars as i32 + 1))
+        .map(|v| if state.value(v) > 0 { v } else { -v })
+        .collect())
+}
+
+struct State {
+    clauses: Vec<Vec<i32>>,
+    watches: Vec<Vec<usize>>,
+    assignment: Vec<i8>,
+    trail: Vec<i32>,
+    propagated: usize,
+    decisions: Vec<(usize, i32, bool)>,
+    order: Vec<i32>,
+}
+
+fn watch_index(lit: i32) -> usize {
+    if lit > 0 { 2 * lit as usize } else { 2 * (-lit) as usize + 1 }
+}
+
+impl State {
+    /// Sets up the search, returning None if the formula is trivially unsatisfiable.
+    fn new(num_vars: usize, clauses: &[Vec<i32>]) -> Option<State> {
+        let mut state = State {
+            clauses: Vec::with_capacity(clauses.len()),
+            watches: vec![Vec::new(); 2 * num_vars + 2],
+            assignment: vec![UNASSIGNED; num_vars + 1],
+            trail: Vec::new(),
+            propagated: 0,
+            decisions: Vec::new(),
+            order: Vec::new(),
+        };
+
+        let mut occurrences = vec![0usize; num_vars + 1];
+        let mut units = Vec::new();
+        for clause in clauses.iter() {
+            let mut c = clause.clone();
+            c.sort();
+            c.dedup();
+            if c.iter().any(|&x| c.binary_search(&-x).is_ok()) {
+                continue;
+            }
+            for &lit in c.iter() {
+                occurrences[lit.unsigned_abs() as usize] += 1;
+            }
+            match c.len() {
+                0 => { return None; }
+                1 => { units.push(c[0]); }
+                _ => {
+                    let idx = state.clauses.len();
+                    state.watches[watch_index(c[0])].push(idx);
+                    state.watches[watch_index(c[1])].push(idx);
+                    state.clauses.push(c);
+                }
+            }
+        }
+
+        state.order = (1..(num_vars as i32 + 1)).collect();
+        state.order.sort_by(|a, b| occurrences[*b as usize].cmp(&occurrences[*a as usize]));
+
+        for lit in units {
+            match state.value(lit) {
+                x if x > 0 => {},
+                x if x < 0 => { return None; },
+                _ => { state.assign(lit); }
+            }
+        }
+        Some(state)
+    }
+
+    /// The value of a literal: 1 if true, -1 if false, 0 if unassigned.
+    fn value(&self, lit: i32) -> i8 {
+        let v = self.assignment[lit.unsigned_abs() as usize];
+        if lit > 0 { v } else { -v }
+    }
+
+    fn assign(&mut self, lit: i32) {
+        self.assignment[lit.unsigned_abs() as usize] = if lit > 0 { 1 } else { -1 };
+        self.trail.push(lit);
+    }
+
+    /// Runs unit propagation, returning false on a conflict.
+    fn propagate(&mut self) -> bool {
+        while self.propagated < self.trail.len() {
+            let false_lit = -self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[watch_index(false_lit)]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = false;
+            for (pos, &idx) in watching.iter().enumerate() {
+                if conflict {
+                    kept.extend_from_slice(&watching[pos..]);
+                    break;
+                }
+                if self.clauses[idx][0] == false_lit {
+                    self.clauses[idx].swap(0, 1);
+                }
+                let other = self.clauses[idx][0];
+                if self.value(other) > 0 {
+                    kept.push(idx);
+                    continue;
+                }
+                let replacement = (2..self.clauses[idx].len())
+                    .find(|&k| self.value(self.clauses[idx][k]) >= 0);
+                match replacement {
+                    Some(k) => {
+                        self.clauses[idx].swap(1, k);
+                        let new_watch = self.clauses[idx][1];
+                        self.watches[watch_index(new_watch)].push(idx);
+                    },
+                    None => {
+                        kept.push(idx);
+                        if self.value(other) < 0 {
+                            conflict = true;
+                        } else {
+                            self.assign(other);
+                        }
+                    }
+                }
+            }
+            self.watches[watch_index(false_lit)] = kept;
+            if conflict {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn undo_to(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            let lit = self.trail.pop().unwrap();
+            self.assignment[lit.unsigned_abs() as usize] = UNASSIGNED;
+        }
+        self.propagated = trail_len;
+    }
+
+    fn search(&mut self) -> bool {
+        loop {
+            if !self.propagate() {
+                loop {
+                    let (trail_len, lit, flipped) = match self.decisions.pop() {
+                        Some(x) => x,
+                        None => { return false; }
+                    };
+                    self.undo_to(trail_len);
+                    if !flipped {
+                        self.decisions.push((trail_len, -lit, true));
+                        self.assign(-lit);
+                        break;
+                    }
+                }
+                continue;
+            }
+            let next = self.order.iter().cloned().find(|&v| self.value(v) == UNASSIGNED);
+            match next {
+                Some(v) => {
+                    self.decisions.push((self.trail.len(), -v, false));
+                    self.assign(-v);
+                },
+                None => { return true; }
+            }
+        }
+    }
+}
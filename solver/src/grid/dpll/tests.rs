@@ -0,0 +1,85 @@
+use super::*;
+use super::super::{IS_LIGHT, solve_puzzle, try_solve};
+use super::super::cnf_format::{make_cnf_formula, populate_grid_with_cnf};
+use super::super::coords::Coord;
+use super::super::rules::is_solved;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+static TEST_GRID_STR: &str =
+"__1_1__
+ _______
+ X_____2
+ _______
+ 0_____0
+ _______
+ __1_0__";
+
+#[test]
+fn test_solve_puzzle_cnf() {
+    let mut grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 7, 7).unwrap());
+    let formula = make_cnf_formula(&grid);
+    let result = solve(&formula).unwrap();
+    assert_eq!(formula.num_variables(), result.len());
+    populate_grid_with_cnf(&mut grid, &formula, result);
+    assert!(is_solved(&grid));
+}
+
+#[test]
+fn test_unsatisfiable() {
+    let grid = precompute_data(get_grid_from_string("_0_", 1, 3).unwrap());
+    assert!(solve(&make_cnf_formula(&grid)).is_err());
+}
+
+#[test]
+fn test_try_solve() {
+    let grid = precompute_data(get_grid_from_string("_0_", 1, 3).unwrap());
+    assert_eq!(Ok(None), try_solve(solve, &make_cnf_formula(&grid)));
+    let grid = precompute_data(get_grid_from_string("_1", 1, 2).unwrap());
+    assert_eq!(Ok(Some(vec![1])), try_solve(solve, &make_cnf_formula(&grid)));
+
+    fn broken(_: &CnfFormula) -> Result<Vec<i32>, String> {
+        Err("Error executing SAT solver".to_string())
+    }
+    assert_eq!(Err("Error executing SAT solver".to_string()), try_solve(broken, &make_cnf_formula(&grid)));
+}
+
+#[test]
+fn test_empty_clause() {
+    let grid = precompute_data(get_grid_from_string("_1_", 1, 3).unwrap());
+    let formula = make_cnf_formula(&grid).append_clause(Vec::new());
+    assert!(solve(&formula).is_err());
+}
+
+#[test]
+fn test_as_puzzle_backend() {
+    let mut grid = precompute_data(get_grid_from_string(
+        "___
+         _3_
+         _X_", 3, 3).unwrap());
+    let (lights, is_unique) = solve_puzzle(&mut grid, solve).unwrap();
+    assert_eq!(vec![Coord::new(0, 1), Coord::new(1, 0), Coord::new(1, 2)], lights);
+    assert!(is_unique);
+    assert!(grid.grid.contents[1] & IS_LIGHT != 0);
+}
+
+#[test]
+fn test_uniqueness() {
+    let mut unique_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 7, 7).unwrap());
+    let mut ambiguous_grid = precompute_data(get_grid_from_string("__\n__", 2, 2).unwrap());
+    assert!(solve_puzzle(&mut unique_grid, solve).unwrap().1);
+    assert!(!solve_puzzle(&mut ambiguous_grid, solve).unwrap().1);
+}
+
+#[test]
+fn test_uniqueness_solver_failure() {
+    // Fails once the first solution has been ruled out, by a clause over every variable.
+    fn fails_on_second(formula: &CnfFormula) -> Result<Vec<i32>, String> {
+        if formula.clauses().iter().any(|x| x.len() == formula.num_variables()) {
+            Err("Error executing SAT solver".to_string())
+        } else {
+            solve(formula)
+        }
+    }
+    let mut grid = precompute_data(get_grid_from_string("__\n__", 2, 2).unwrap());
+    assert_eq!(Err("Error executing SAT solver".to_string()), solve_puzzle(&mut grid, fails_on_second));
+}
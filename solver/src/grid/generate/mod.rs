@@ -0,0 +1,516 @@
+use super::{IS_SOLID, IS_LIGHT, IS_CONSTRAINED, INVALID_POSITION};
+use super::{Grid, GridData, SatSolver};
+use super::clues::{is_unique, redundant_clues};
+use super::grade::{grade_puzzle, Difficulty};
+use super::utils::{get_neighbors, insert_light, precompute_data};
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// How many random layouts to try before settling for the best one found.
+const NUM_RANDOM_GRIDS_TO_TRY: usize = 40;
+/// How many ways of placing lights to try for a given layout.
+const NUM_LIGHTINGS_TO_TRY: usize = 200;
+/// A puzzle with fewer clues per wall than this is good enough to stop at.
+const CONSTRAINED_GRID_RATIO_THRESHOLD: f64 = 0.4;
+
+/// What kind of puzzle to generate.
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    pub height: i32,
+    pub width: i32,
+    /// The chance of each square being a wall.
+    pub density: f64,
+    /// The symmetry of the walls.
+    pub symmetry: Symmetry,
+    /// Whether clues are kept or taken away together with the clues their
+    /// walls are symmetric to, so that where the clues are is symmetric too.
+    pub symmetric_clues: bool,
+    /// The difficulty to search for, if any.
+    pub difficulty: Option<Difficulty>,
+    /// How long to search for the difficulty before settling for the closest.
+    pub time_limit: Duration,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> GenerateOptions {
+        GenerateOptions {
+            height: 5,
+            width: 5,
+            density: 0.35,
+            symmetry: Symmetry::None,
+            symmetric_clues: false,
+            difficulty: None,
+            time_limit: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A symmetry of the layout of walls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// The same when turned upside down.
+    Rotate180,
+    /// The same when turned a quarter, which needs a square grid.
+    Rotate90,
+    /// The left half mirrors the right half.
+    Horizontal,
+    /// The top half mirrors the bottom half.
+    Vertical,
+    /// Mirrored along the diagonal from the top left, which needs a square grid.
+    Diagonal,
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Symmetry, String> {
+        match s {
+            "none" => Ok(Symmetry::None),
+            "rotate180" => Ok(Symmetry::Rotate180),
+            "rotate90" => Ok(Symmetry::Rotate90),
+            "horizontal" => Ok(Symmetry::Horizontal),
+            "vertical" => Ok(Symmetry::Vertical),
+            "diagonal" => Ok(Symmetry::Diagonal),
+            _ => Err(format!("Unknown symmetry {}, expected none, rotate180, rotate90, \
+                              horizontal, vertical or diagonal", s)),
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Symmetry::None => "none",
+            Symmetry::Rotate180 => "rotate180",
+            Symmetry::Rotate90 => "rotate90",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Diagonal => "diagonal",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Symmetry {
+    /**
+     * Splits the squares of a grid of the given size into orbits, the sets of
+     * squares the symmetry takes into each other, each sorted and in order of
+     * their first square. Returns an error if the symmetry needs a square
+     * grid and the grid isn't square.
+     */
+    pub fn orbits(self, height: i32, width: i32) -> Result<Vec<Vec<usize>>, String> {
+        if height != width && (self == Symmetry::Rotate90 || self == Symmetry::Diagonal) {
+            return Err(format!("A {}x{} grid can't have {} symmetry", height, width, self));
+        }
+        let size = (height * width) as usize;
+        let mut seen = vec![false; size];
+        let mut orbits = Vec::new();
+        for start in 0..size {
+            if seen[start] {
+                continue;
+            }
+            let mut orbit = Vec::new();
+            let mut loc = start;
+            while !seen[loc] {
+                seen[loc] = true;
+                orbit.push(loc);
+                loc = self.image(loc, height, width);
+            }
+            orbit.sort();
+            orbits.push(orbit);
+        }
+        Ok(orbits)
+    }
+
+    /// Where the symmetry takes a square.
+    fn image(self, loc: usize, height: i32, width: i32) -> usize {
+        let (row, col) = (loc as i32 / width, loc as i32 % width);
+        let (row, col) = match self {
+            Symmetry::None => (row, col),
+            Symmetry::Rotate180 => (height - 1 - row, width - 1 - col),
+            Symmetry::Rotate90 => (col, width - 1 - row),
+            Symmetry::Horizontal => (row, width - 1 - col),
+            Symmetry::Vertical => (height - 1 - row, col),
+            Symmetry::Diagonal => (col, row),
+        };
+        (row * width + col) as usize
+    }
+}
+
+/// A generated puzzle, with a unique solution.
+#[derive(Clone)]
+pub struct Generated {
+    pub puzzle: Grid,
+    /// The fraction of walls that carry a clue.
+    pub ratio: f64,
+    pub difficulty: Difficulty,
+    /// Whether every clue is needed for the solution to be unique.
+    pub minimal: bool,
+}
+
+/**
+ * A small xorshift64* pseudo-random number generator. The same seed always
+ * gives the same numbers, on every platform.
+ */
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Scramble the seed with SplitMix64, since xorshift gets stuck at zero
+        // and starts out poorly from seeds with few bits set.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Rng { state: if z == 0 { 1 } else { z } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number from 0 up to but not including n, which must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            let other = self.below(idx + 1);
+            items.swap(idx, other);
+        }
+    }
+}
+
+/**
+ * Generates a puzzle with a unique solution, the same way the player's
+ * generator does. For each random layout, lights are placed greedily at
+ * random until every square is lit, and every wall next to an empty square
+ * gets the clue those lights give it. Clues are then taken away, first by
+ * a binary search for the fewest that keep the solution unique, and then
+ * one at a time in random order, until every clue left is needed. With
+ * symmetric clues, clues are taken away a whole orbit at a time instead,
+ * so a clue may be left that isn't needed on its own, and the puzzle isn't
+ * tagged as minimal.
+ *
+ * Without a difficulty, the layout with the lowest ratio of clues to walls
+ * is kept, stopping early once it is low enough. With one, clues are only
+ * taken away while the grade stays at or below it, and layouts are tried
+ * until one has exactly that grade, or until the time limit is up, when
+ * the closest one found is returned. Clues stop being taken away when the
+ * time is up, so that puzzle may have more of them than it needs. Returns
+ * an error if no layout had a unique solution.
+ */
+pub fn generate_puzzle(options: &GenerateOptions, rng: &mut Rng, solver: SatSolver)
+        -> Result<Generated, String> {
+    let (height, width) = (options.height, options.width);
+    if height <= 0 || width <= 0 {
+        return Err(format!("Invalid dimensions {}x{}", height, width));
+    }
+    let orbits = options.symmetry.orbits(height, width)?;
+    let new_layout = |rng: &mut Rng| random_walls(height, width, options.density, &orbits, rng);
+    let (best, num_tries) = search_layouts(options, &orbits, NUM_RANDOM_GRIDS_TO_TRY, new_layout, rng, solver)?;
+    best.ok_or_else(|| format!("No unique {}x{} puzzle found in {} tries", height, width, num_tries))
+}
+
+/**
+ * Finds clues for the walls of the given layout that make its solution
+ * unique, in the same way as generate_puzzle, except that the walls stay
+ * where they are and only the lights are placed anew for each try. Any
+ * clues, lights or marks in the layout are ignored, and so are the size
+ * and density in the options. Returns an error if the walls don't have the
+ * symmetry in the options, or if none of the ways of placing lights tried
+ * gave clues with a unique solution. Lights placed some other way might
+ * still have given some, since not every way is tried.
+ */
+pub fn generate_for_layout(layout: &Grid, options: &GenerateOptions, rng: &mut Rng, solver: SatSolver)
+        -> Result<Generated, String> {
+    let (height, width) = (layout.height, layout.width);
+    let orbits = options.symmetry.orbits(height, width)?;
+    let walls: Vec<u8> = layout.contents.iter().map(|&x| x & IS_SOLID).collect();
+    if orbits.iter().any(|x| x.iter().any(|&loc| walls[loc] != walls[x[0]])) {
+        return Err(format!("The walls don't have {} symmetry", options.symmetry));
+    }
+    let walls = precompute_data(Grid { contents: walls, height, width });
+    let (best, num_tries) = search_layouts(options, &orbits, NUM_LIGHTINGS_TO_TRY, |_| walls.clone(), rng, solver)?;
+    best.ok_or_else(|| format!("No clues that make the solution unique found in {} ways of placing lights",
+                                 num_tries))
+}
+
+/**
+ * Lights the layouts it is given and searches for clues for them, keeping
+ * the best puzzle found as described for generate_puzzle. Returns it, if
+ * there was one, with the number of layouts tried.
+ */
+fn search_layouts<F>(options: &GenerateOptions, orbits: &[Vec<usize>], max_tries: usize, mut new_layout: F,
+                     rng: &mut Rng, solver: SatSolver) -> Result<(Option<Generated>, usize), String>
+        where F: FnMut(&mut Rng) -> GridData {
+    let start = Instant::now();
+    let accept = Acceptance {
+        difficulty: options.difficulty,
+        deadline: options.difficulty.map(|_| start + options.time_limit),
+        solver,
+    };
+    let mut best: Option<Candidate> = None;
+    let mut num_tries = 0;
+    loop {
+        let out_of_tries = match options.difficulty {
+            None => num_tries >= max_tries,
+            Some(_) => num_tries > 0 && start.elapsed() >= options.time_limit,
+        };
+        if out_of_tries {
+            break;
+        }
+        num_tries += 1;
+
+        let mut grid = new_layout(rng);
+        populate_with_lights(&mut grid, rng);
+        let groups = clue_groups(orbits, options.symmetric_clues);
+        let puzzle = match search_constraints(&grid, &groups, &accept, rng)? {
+            Some(x) => x,
+            None => { continue; }
+        };
+        // Grading is only worth its cost when there is a difficulty to compare with.
+        let difficulty = match options.difficulty {
+            Some(_) => Some(grade_puzzle(&precompute_data(puzzle.clone()))?.difficulty),
+            None => None,
+        };
+        let candidate = Candidate { ratio: constraint_ratio(&puzzle), difficulty, puzzle };
+        let good_enough = match options.difficulty {
+            None => candidate.ratio < CONSTRAINED_GRID_RATIO_THRESHOLD,
+            Some(target) => candidate.difficulty == Some(target),
+        };
+        if best.as_ref().is_none_or(|x| is_closer(&candidate, x, options.difficulty)) {
+            best = Some(candidate);
+        }
+        if good_enough {
+            break;
+        }
+    }
+    let best = match best {
+        Some(x) => x,
+        None => { return Ok((None, num_tries)); }
+    };
+    let difficulty = match best.difficulty {
+        Some(x) => x,
+        None => grade_puzzle(&precompute_data(best.puzzle.clone()))?.difficulty,
+    };
+    let generated = Generated {
+        minimal: redundant_clues(&best.puzzle, solver)?.is_empty(),
+        puzzle: best.puzzle,
+        ratio: best.ratio,
+        difficulty,
+    };
+    Ok((Some(generated), num_tries))
+}
+
+/// A puzzle found for one layout, graded only when there is a difficulty to search for.
+struct Candidate {
+    puzzle: Grid,
+    ratio: f64,
+    difficulty: Option<Difficulty>,
+}
+
+/// Whether a puzzle is closer to the difficulty than another, or has fewer clues if they are as close.
+fn is_closer(puzzle: &Candidate, other: &Candidate, target: Option<Difficulty>) -> bool {
+    let distance = |x: &Candidate| match (target, x.difficulty) {
+        (Some(t), Some(d)) => (d as i32 - t as i32).abs(),
+        _ => 0,
+    };
+    (distance(puzzle), puzzle.ratio) < (distance(other), other.ratio)
+}
+
+/// The fraction of walls that carry a clue, or 0 if there are no walls.
+pub fn constraint_ratio(grid: &Grid) -> f64 {
+    let num_solid = grid.contents.iter().filter(|&&x| x & IS_SOLID != 0).count();
+    let num_constrained = grid.contents.iter().filter(|&&x| x & IS_CONSTRAINED != 0).count();
+    if num_solid == 0 { 0.0 } else { num_constrained as f64 / num_solid as f64 }
+}
+
+/// Makes each orbit all walls with the given chance, and all empty otherwise.
+fn random_walls(height: i32, width: i32, density: f64, orbits: &[Vec<usize>], rng: &mut Rng) -> GridData {
+    let mut contents = vec![0; (height * width) as usize];
+    for orbit in orbits {
+        if rng.chance(density) {
+            for &loc in orbit {
+                contents[loc] = IS_SOLID;
+            }
+        }
+    }
+    precompute_data(Grid { contents, height, width })
+}
+
+/// The sets of squares whose clues are taken away together.
+fn clue_groups(orbits: &[Vec<usize>], symmetric_clues: bool) -> Vec<Vec<usize>> {
+    if symmetric_clues {
+        orbits.to_vec()
+    } else {
+        orbits.iter().flatten().map(|&x| vec![x]).collect()
+    }
+}
+
+/// Places lights on random unlit squares until every square is lit.
+fn populate_with_lights(grid: &mut GridData, rng: &mut Rng) {
+    let mut unlit: BTreeSet<usize> = (0..grid.grid.contents.len())
+        .filter(|&x| grid.grid.contents[x] & IS_SOLID == 0)
+        .collect();
+    while !unlit.is_empty() {
+        let loc = *unlit.iter().nth(rng.below(unlit.len())).unwrap();
+        insert_light(grid, loc);
+        unlit.remove(&loc);
+        for other in grid.sight_lines[&loc].iter() {
+            unlit.remove(other);
+        }
+    }
+}
+
+/**
+ * The walls and clues of a grid with lights in it, where every wall next to
+ * at least one empty square gets a clue counting the lights next to it.
+ */
+fn set_constraints_full(grid: &GridData) -> Grid {
+    let mut contents = Vec::with_capacity(grid.grid.contents.len());
+    for (loc, &square) in grid.grid.contents.iter().enumerate() {
+        if square & IS_SOLID == 0 {
+            contents.push(0);
+            continue;
+        }
+        let neighbors: Vec<usize> = get_neighbors(grid, loc).1[..4].iter().cloned()
+            .filter(|&x| x != INVALID_POSITION && grid.grid.contents[x] & IS_SOLID == 0)
+            .collect();
+        if neighbors.is_empty() {
+            contents.push(IS_SOLID);
+        } else {
+            let num_lights = neighbors.iter().filter(|&&x| grid.grid.contents[x] & IS_LIGHT != 0).count();
+            contents.push(num_lights as u8 | IS_SOLID | IS_CONSTRAINED);
+        }
+    }
+    Grid { contents, height: grid.grid.height, width: grid.grid.width }
+}
+
+/**
+ * Takes as many clues as it can out of the fully clued puzzle for the lit
+ * grid while keeping it acceptable. Returns None if it isn't unique even
+ * with every clue, and the fully clued puzzle if that is already too hard.
+ * The clues of each group of squares are kept or taken away together. Once
+ * the deadline of the acceptance has passed, no more clues are taken away.
+ */
+fn search_constraints(grid: &GridData, groups: &[Vec<usize>], accept: &Acceptance, rng: &mut Rng)
+        -> Result<Option<Grid>, String> {
+    let full = set_constraints_full(grid);
+    if !is_unique(&full, accept.solver)? {
+        return Ok(None);
+    }
+    if !accept.check(&full)? {
+        return Ok(Some(full));
+    }
+    let mut groups: Vec<Vec<usize>> = groups.iter()
+        .map(|x| x.iter().cloned().filter(|&loc| full.contents[loc] & IS_CONSTRAINED != 0).collect())
+        .filter(|x: &Vec<usize>| !x.is_empty())
+        .collect();
+    rng.shuffle(&mut groups);
+
+    let mut puzzle = full.clone();
+    let num_kept = binary_search_to_constraints(&mut puzzle, &full, &groups, accept)?;
+    remove_redundant_constraints(&mut puzzle, &full, &groups[..num_kept], accept)?;
+    Ok(Some(puzzle))
+}
+
+/**
+ * Finds a number n such that keeping only the first n groups of clues of the
+ * fully clued puzzle leaves it acceptable, but keeping n - 1 of them
+ * doesn't, and leaves the puzzle with those n groups. The fully clued
+ * puzzle is assumed to be acceptable. If the deadline passes first, the
+ * fewest groups found to be acceptable so far are kept instead.
+ */
+fn binary_search_to_constraints(puzzle: &mut Grid, full: &Grid, groups: &[Vec<usize>],
+                                accept: &Acceptance) -> Result<usize, String> {
+    let (mut lower, mut upper) = (0, groups.len());
+    while lower < upper && !accept.is_past_deadline() {
+        let mid = lower + (upper - lower) / 2;
+        set_num_constraints(puzzle, full, groups, mid);
+        if accept.check(puzzle)? {
+            upper = mid;
+        } else {
+            lower = mid + 1;
+        }
+    }
+    set_num_constraints(puzzle, full, groups, upper);
+    Ok(upper)
+}
+
+/// Keeps the clues of the first num_constrained groups, and takes the rest away.
+fn set_num_constraints(puzzle: &mut Grid, full: &Grid, groups: &[Vec<usize>], num_constrained: usize) {
+    for (idx, group) in groups.iter().enumerate() {
+        for &loc in group {
+            puzzle.contents[loc] = if idx < num_constrained { full.contents[loc] } else { IS_SOLID };
+        }
+    }
+}
+
+/**
+ * Takes each of the groups of clues away in turn, putting it back if the
+ * puzzle stops being acceptable. Since taking clues away never makes a
+ * group that was needed unneeded, every group left is needed afterwards,
+ * unless the deadline passed before every group was tried.
+ */
+fn remove_redundant_constraints(puzzle: &mut Grid, full: &Grid, groups: &[Vec<usize>],
+                                accept: &Acceptance) -> Result<(), String> {
+    for group in groups {
+        if accept.is_past_deadline() {
+            break;
+        }
+        for &loc in group {
+            puzzle.contents[loc] = IS_SOLID;
+        }
+        if !accept.check(puzzle)? {
+            for &loc in group {
+                puzzle.contents[loc] = full.contents[loc];
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What a puzzle with fewer clues has to be for them to stay away.
+struct Acceptance {
+    difficulty: Option<Difficulty>,
+    /// When to stop taking clues away, if ever.
+    deadline: Option<Instant>,
+    solver: SatSolver,
+}
+
+impl Acceptance {
+    fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|x| Instant::now() >= x)
+    }
+
+    /// Whether the solution is unique, and the grade no harder than the difficulty.
+    fn check(&self, puzzle: &Grid) -> Result<bool, String> {
+        if !is_unique(puzzle, self.solver)? {
+            return Ok(false);
+        }
+        match self.difficulty {
+            Some(difficulty) => Ok(grade_puzzle(&precompute_data(puzzle.clone()))?.difficulty <= difficulty),
+            None => Ok(true),
+        }
+    }
+}
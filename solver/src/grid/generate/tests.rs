@@ -0,0 +1,245 @@
+use super::*;
+use super::super::IS_LIT;
+use super::super::dpll::solve;
+use super::super::grade::grade_puzzle;
+use super::super::solve_puzzle;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+#[test]
+fn test_rng_is_reproducible() {
+    let mut first = Rng::new(42);
+    let mut second = Rng::new(42);
+    let mut other = Rng::new(43);
+    let first_values: Vec<u64> = (0..5).map(|_| first.next_u64()).collect();
+    assert_eq!(first_values, (0..5).map(|_| second.next_u64()).collect::<Vec<_>>());
+    assert_ne!(first_values, (0..5).map(|_| other.next_u64()).collect::<Vec<_>>());
+    assert_ne!(0, Rng::new(0).next_u64());
+}
+
+#[test]
+fn test_rng_ranges() {
+    let mut rng = Rng::new(7);
+    let mut seen = [false; 5];
+    for _ in 0..200 {
+        seen[rng.below(5)] = true;
+    }
+    assert!(seen.iter().all(|&x| x));
+    assert!((0..100).all(|_| !rng.chance(0.0)));
+    assert!((0..100).all(|_| rng.chance(1.0)));
+
+    let mut items: Vec<usize> = (0..20).collect();
+    rng.shuffle(&mut items);
+    assert_ne!((0..20).collect::<Vec<_>>(), items);
+    items.sort();
+    assert_eq!((0..20).collect::<Vec<_>>(), items);
+}
+
+#[test]
+fn test_set_constraints_full() {
+    let grid = precompute_data(get_grid_from_string("*X#X\nXXX_\n#__*", 3, 4).unwrap());
+    let puzzle = set_constraints_full(&grid);
+    assert_eq!("_1_0100_____", print_grid_to_string(&puzzle, false));
+}
+
+#[test]
+fn test_populate_with_lights() {
+    let orbits = Symmetry::None.orbits(6, 6).unwrap();
+    let mut grid = random_walls(6, 6, 0.3, &orbits, &mut Rng::new(5));
+    populate_with_lights(&mut grid, &mut Rng::new(6));
+    assert!(grid.grid.contents.iter().all(|&x| x & IS_SOLID != 0 || x & (IS_LIGHT | IS_LIT) != 0));
+    let lights: Vec<usize> = (0..36).filter(|&x| grid.grid.contents[x] & IS_LIGHT != 0).collect();
+    assert!(lights.iter().all(|x| lights.iter().all(|y| !grid.sight_lines[x].contains(y))));
+}
+
+#[test]
+fn test_generate_puzzle() {
+    let options = GenerateOptions::default();
+    let generated = generate_puzzle(&options, &mut Rng::new(1), solve).unwrap();
+    let puzzle = generated.puzzle;
+    assert_eq!((5, 5), (puzzle.height, puzzle.width));
+    assert!(puzzle.contents.iter().all(|&x| x & (IS_LIGHT | IS_LIT) == 0));
+    assert_eq!(constraint_ratio(&puzzle), generated.ratio);
+    assert!(generated.minimal);
+    assert!(redundant_clues(&puzzle, solve).unwrap().is_empty());
+    assert_eq!(grade_puzzle(&precompute_data(puzzle.clone())).unwrap().difficulty, generated.difficulty);
+    assert!(is_unique(&puzzle, solve).unwrap());
+
+    let again = generate_puzzle(&options, &mut Rng::new(1), solve).unwrap().puzzle;
+    assert_eq!(print_grid_to_string(&puzzle, false), print_grid_to_string(&again, false));
+    let other = generate_puzzle(&options, &mut Rng::new(2), solve).unwrap().puzzle;
+    assert_ne!(print_grid_to_string(&puzzle, false), print_grid_to_string(&other, false));
+    let options = GenerateOptions { height: 0, ..GenerateOptions::default() };
+    assert!(generate_puzzle(&options, &mut Rng::new(1), solve).is_err());
+}
+
+#[test]
+fn test_binary_search_to_constraints() {
+    // The 3 alone forces the lights, and every other clue is redundant.
+    let full = get_grid_from_string("2_2_3_1X_", 3, 3).unwrap();
+    let mut puzzle = full.clone();
+    let groups = [vec![4], vec![0, 2], vec![6]];
+    let accept = Acceptance { difficulty: None, deadline: None, solver: solve };
+    assert_eq!(1, binary_search_to_constraints(&mut puzzle, &full, &groups, &accept).unwrap());
+    assert_eq!("X_X_3_XX_", print_grid_to_string(&puzzle, false));
+}
+
+#[test]
+fn test_remove_redundant_constraints() {
+    let full = get_grid_from_string("2_2_3_1X_", 3, 3).unwrap();
+    let mut puzzle = full.clone();
+    let groups = [vec![0], vec![2], vec![4], vec![6]];
+    let accept = Acceptance { difficulty: None, deadline: None, solver: solve };
+    remove_redundant_constraints(&mut puzzle, &full, &groups, &accept).unwrap();
+    assert_eq!("X_X_3_XX_", print_grid_to_string(&puzzle, false));
+
+    let mut puzzle = full.clone();
+    let groups = [vec![4], vec![0, 2], vec![6]];
+    remove_redundant_constraints(&mut puzzle, &full, &groups, &accept).unwrap();
+    assert_eq!("2_2_X_XX_", print_grid_to_string(&puzzle, false));
+}
+
+#[test]
+fn test_past_deadline_keeps_clues() {
+    let full = get_grid_from_string("2_2_3_1X_", 3, 3).unwrap();
+    let mut puzzle = full.clone();
+    let groups = [vec![4], vec![0, 2], vec![6]];
+    let accept = Acceptance { difficulty: None, deadline: Some(Instant::now()), solver: solve };
+    assert_eq!(3, binary_search_to_constraints(&mut puzzle, &full, &groups, &accept).unwrap());
+    remove_redundant_constraints(&mut puzzle, &full, &groups, &accept).unwrap();
+    assert_eq!(print_grid_to_string(&full, false), print_grid_to_string(&puzzle, false));
+}
+
+#[test]
+fn test_generated_puzzles_have_few_clues() {
+    let options = GenerateOptions { height: 7, width: 7, density: 0.3, ..GenerateOptions::default() };
+    let generated = generate_puzzle(&options, &mut Rng::new(11), solve).unwrap();
+    let (puzzle, ratio) = (generated.puzzle, generated.ratio);
+    let mut full = precompute_data(puzzle.clone());
+    solve_puzzle(&mut full, solve).unwrap();
+    assert!(ratio < constraint_ratio(&set_constraints_full(&full)) || ratio == 0.0);
+}
+
+#[test]
+fn test_constraint_ratio() {
+    assert_eq!(0.5, constraint_ratio(&get_grid_from_string("X1__", 2, 2).unwrap()));
+    assert_eq!(0.0, constraint_ratio(&get_grid_from_string("____", 2, 2).unwrap()));
+}
+
+#[test]
+fn test_symmetry_orbits() {
+    assert_eq!(vec![vec![0], vec![1], vec![2], vec![3]], Symmetry::None.orbits(2, 2).unwrap());
+    assert_eq!(vec![vec![0, 5], vec![1, 4], vec![2, 3]], Symmetry::Rotate180.orbits(2, 3).unwrap());
+    assert_eq!(vec![vec![0, 2], vec![1], vec![3, 5], vec![4]], Symmetry::Horizontal.orbits(2, 3).unwrap());
+    assert_eq!(vec![vec![0, 3], vec![1, 4], vec![2, 5]], Symmetry::Vertical.orbits(2, 3).unwrap());
+    assert_eq!(vec![vec![0, 2, 6, 8], vec![1, 3, 5, 7], vec![4]], Symmetry::Rotate90.orbits(3, 3).unwrap());
+    assert_eq!(vec![vec![0], vec![1, 3], vec![2, 6], vec![4], vec![5, 7], vec![8]],
+               Symmetry::Diagonal.orbits(3, 3).unwrap());
+    assert!(Symmetry::Rotate90.orbits(2, 3).is_err());
+    assert!(Symmetry::Diagonal.orbits(3, 2).is_err());
+    assert_eq!(Ok(Symmetry::Rotate180), "rotate180".parse::<Symmetry>());
+    assert!("sideways".parse::<Symmetry>().is_err());
+}
+
+#[test]
+fn test_symmetric_walls() {
+    for &symmetry in [Symmetry::Rotate180, Symmetry::Rotate90, Symmetry::Horizontal,
+                      Symmetry::Vertical, Symmetry::Diagonal].iter() {
+        let options = GenerateOptions { height: 6, width: 6, symmetry, ..GenerateOptions::default() };
+        let puzzle = generate_puzzle(&options, &mut Rng::new(4), solve).unwrap().puzzle;
+        for orbit in symmetry.orbits(6, 6).unwrap() {
+            let is_solid = |x: &usize| puzzle.contents[*x] & IS_SOLID != 0;
+            assert!(orbit.iter().all(is_solid) || !orbit.iter().any(is_solid), "{:?}", symmetry);
+        }
+    }
+}
+
+#[test]
+fn test_symmetric_clues() {
+    let options = GenerateOptions {
+        height: 6,
+        width: 6,
+        symmetry: Symmetry::Rotate180,
+        symmetric_clues: true,
+        ..GenerateOptions::default()
+    };
+    for seed in 0..3 {
+        let puzzle = generate_puzzle(&options, &mut Rng::new(seed), solve).unwrap().puzzle;
+        assert!(is_unique(&puzzle, solve).unwrap());
+        for loc in 0..36 {
+            let is_clue = |x: usize| puzzle.contents[x] & IS_CONSTRAINED != 0;
+            assert_eq!(is_clue(loc), is_clue(35 - loc));
+        }
+    }
+}
+
+#[test]
+fn test_target_difficulty() {
+    for &difficulty in [Difficulty::Easy, Difficulty::Medium].iter() {
+        let options = GenerateOptions {
+            height: 6,
+            width: 6,
+            difficulty: Some(difficulty),
+            ..GenerateOptions::default()
+        };
+        let generated = generate_puzzle(&options, &mut Rng::new(8), solve).unwrap();
+        assert_eq!(difficulty, generated.difficulty);
+        assert_eq!(difficulty, grade_puzzle(&precompute_data(generated.puzzle)).unwrap().difficulty);
+    }
+}
+
+#[test]
+fn test_target_difficulty_time_limit() {
+    // With no time at all, only one layout is tried, and it is returned however far off it is.
+    let options = GenerateOptions {
+        height: 4,
+        width: 4,
+        difficulty: Some(Difficulty::Expert),
+        time_limit: Duration::from_secs(0),
+        ..GenerateOptions::default()
+    };
+    let generated = generate_puzzle(&options, &mut Rng::new(1), solve).unwrap();
+    assert!(is_unique(&generated.puzzle, solve).unwrap());
+}
+
+#[test]
+fn test_acceptance() {
+    let hard = get_grid_from_string("__1_1_________X_____2_______0_____0_________1_0__", 7, 7).unwrap();
+    for &(difficulty, accepted) in [(Difficulty::Medium, false), (Difficulty::Hard, true),
+                                    (Difficulty::Expert, true)].iter() {
+        let accept = Acceptance { difficulty: Some(difficulty), deadline: None, solver: solve };
+        assert_eq!(accepted, accept.check(&hard).unwrap());
+    }
+    assert!(Acceptance { difficulty: None, deadline: None, solver: solve }.check(&hard).unwrap());
+    let ambiguous = get_grid_from_string("____", 2, 2).unwrap();
+    assert!(!Acceptance { difficulty: None, deadline: None, solver: solve }.check(&ambiguous).unwrap());
+}
+
+#[test]
+fn test_generate_for_layout() {
+    let layout = get_grid_from_string("X___X\n__X__\n_XXX_\n__X__\nX___X", 5, 5).unwrap();
+    let options = GenerateOptions { symmetry: Symmetry::Rotate90, ..GenerateOptions::default() };
+    let generated = generate_for_layout(&layout, &options, &mut Rng::new(3), solve).unwrap();
+    let is_solid = |grid: &Grid| grid.contents.iter().map(|&x| x & IS_SOLID != 0).collect::<Vec<_>>();
+    assert_eq!(is_solid(&layout), is_solid(&generated.puzzle));
+    assert!(is_unique(&generated.puzzle, solve).unwrap());
+    assert!(generated.minimal);
+
+    // Clues and lights in the layout make no difference.
+    let marked = get_grid_from_string("0*__X\n__4__\n_XXX_\n__X__\nX___1", 5, 5).unwrap();
+    let again = generate_for_layout(&marked, &options, &mut Rng::new(3), solve).unwrap();
+    assert_eq!(print_grid_to_string(&generated.puzzle, false), print_grid_to_string(&again.puzzle, false));
+}
+
+#[test]
+fn test_generate_for_impossible_layout() {
+    // No clue can tell which corners of the open square hold the lights.
+    let layout = get_grid_from_string("__\n__", 2, 2).unwrap();
+    let error = generate_for_layout(&layout, &GenerateOptions::default(), &mut Rng::new(1), solve);
+    assert_eq!("No clues that make the solution unique found in 200 ways of placing lights",
+               error.err().unwrap());
+
+    let layout = get_grid_from_string("X__\n___\n___", 3, 3).unwrap();
+    let options = GenerateOptions { symmetry: Symmetry::Rotate180, ..GenerateOptions::default() };
+    assert_eq!("The walls don't have rotate180 symmetry",
+               generate_for_layout(&layout, &options, &mut Rng::new(1), solve).err().unwrap());
+}
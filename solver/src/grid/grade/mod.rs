@@ -0,0 +1,205 @@
+use super::{IS_SOLID, IS_LIT, IS_LIGHT, CANT_LIGHT};
+use super::GridData;
+use super::rules::{self, Rule};
+use super::utils::insert_light;
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+const CANNOT_BE_LIGHT: u8 = IS_SOLID | IS_LIT | IS_LIGHT | CANT_LIGHT;
+/// The most nested guesses the search makes. Anything past a single guess
+/// is already Expert, so going deeper only costs time.
+pub const MAX_SEARCH_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    /// Solved by the clue rules and the lone square and single source rules.
+    Easy,
+    /// Solved by the rules, but only with the help of the corner or pattern rules.
+    Medium,
+    /// Needs one level of lookahead on top of the rules.
+    Hard,
+    /// Needs a search.
+    Expert,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err(format!("Unknown difficulty {}, expected easy, medium, hard or expert", s)),
+        }
+    }
+}
+
+/// Everything the grader measured about a puzzle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Grade {
+    /// Whether populate_with_rules alone solves the puzzle.
+    pub solved_by_rules: bool,
+    /// Whether the rules with one level of lookahead solve the puzzle.
+    pub solved_by_lookahead: bool,
+    /// How often each rule fired, including lookahead, before the rules got stuck.
+    pub rule_counts: BTreeMap<Rule, usize>,
+    /// The number of passes over the grid the rules needed.
+    pub rounds: usize,
+    /// The fewest nested guesses needed to reach a solution, or
+    /// MAX_SEARCH_DEPTH if a solution takes more than that.
+    pub search_depth: usize,
+    /// The number of positions visited while searching, over every depth tried.
+    pub search_nodes: usize,
+    pub difficulty: Difficulty,
+}
+
+/**
+ * Grades the given puzzle by how much work it takes to solve by hand.
+ * The rules are applied first, then the rules with one level of lookahead.
+ * Separately, a search starting from where the rules got stuck guesses the
+ * state of one square at a time and reapplies the rules after every guess.
+ * The search is repeated with one more level of guesses allowed each time,
+ * so the depth it reports is the least that reaches a solution, whichever
+ * way the guesses are tried. Since the work grows exponentially with the
+ * depth, the search stops at MAX_SEARCH_DEPTH. Returns an error if the
+ * puzzle has no solution and the search finds that out within that depth.
+ */
+pub fn grade_puzzle(grid: &GridData) -> Result<Grade, String> {
+    let mut working = grid.clone();
+    rules::populate_with_rules(&mut working);
+    let mut search_nodes = 0;
+    let mut search_depth = 0;
+    loop {
+        match search(&working, search_depth, &mut search_nodes) {
+            Search::Solved => { break; },
+            Search::CutOff if search_depth == MAX_SEARCH_DEPTH => { break; },
+            Search::CutOff => { search_depth += 1; },
+            Search::NoSolution => { return Err("Puzzle has no solution".to_string()); }
+        }
+    }
+
+    let mut lookahead_working = grid.clone();
+    let trace = rules::populate_with_lookahead_traced(&mut lookahead_working, 1);
+    let mut rule_counts = BTreeMap::new();
+    for deduction in trace.deductions.iter() {
+        *rule_counts.entry(deduction.rule).or_insert(0) += 1;
+    }
+    let solved_by_lookahead = rules::is_solved(&lookahead_working);
+    let solved_by_rules = solved_by_lookahead && !rule_counts.contains_key(&Rule::Lookahead);
+
+    let uses_advanced_rules = rule_counts.keys().any(|r| !matches!(*r,
+        Rule::ClueSatisfied | Rule::ClueFilled | Rule::LoneSquare | Rule::SingleSource));
+    let difficulty = if solved_by_rules && uses_advanced_rules {
+        Difficulty::Medium
+    } else if solved_by_rules {
+        Difficulty::Easy
+    } else if solved_by_lookahead {
+        Difficulty::Hard
+    } else {
+        Difficulty::Expert
+    };
+
+    Ok(Grade {
+        solved_by_rules,
+        solved_by_lookahead,
+        rule_counts,
+        rounds: trace.rounds,
+        search_depth,
+        search_nodes,
+        difficulty,
+    })
+}
+
+/// How a search with limited depth ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Search {
+    Solved,
+    /// No solution was found, but there were guesses left to make.
+    CutOff,
+    NoSolution,
+}
+
+/**
+ * Searches for a solution starting from a grid that the rules have already
+ * been applied to, making at most the given number of nested guesses.
+ */
+fn search(grid: &GridData, limit: usize, nodes: &mut usize) -> Search {
+    *nodes += 1;
+    if rules::has_contradiction(grid) {
+        return Search::NoSolution;
+    }
+    if rules::is_solved(grid) {
+        return Search::Solved;
+    }
+    let candidate = match pick_candidate(grid) {
+        Some(x) => x,
+        None => { return Search::NoSolution; }
+    };
+    if limit == 0 {
+        return Search::CutOff;
+    }
+
+    let mut guesses = Vec::with_capacity(2);
+    let mut with_light = grid.clone();
+    if insert_light(&mut with_light, candidate) {
+        guesses.push(with_light);
+    }
+    let mut without_light = grid.clone();
+    without_light.grid.contents[candidate] |= CANT_LIGHT;
+    guesses.push(without_light);
+
+    let mut result = Search::NoSolution;
+    for mut guess in guesses {
+        rules::populate_with_rules(&mut guess);
+        match search(&guess, limit - 1, nodes) {
+            Search::Solved => { return Search::Solved; },
+            Search::CutOff => { result = Search::CutOff; },
+            Search::NoSolution => { }
+        }
+    }
+    result
+}
+
+/// Picks a square to guess on: one of the sources of the unlit square with the fewest.
+fn pick_candidate(grid: &GridData) -> Option<usize> {
+    let contents = &grid.grid.contents;
+    let mut best: Option<Vec<usize>> = None;
+    for loc in 0..contents.len() {
+        if contents[loc] & (IS_SOLID | IS_LIT) != 0 {
+            continue;
+        }
+        let mut sources: Vec<usize> = match grid.sight_lines.get(&loc) {
+            Some(sl) => sl.iter().filter(|&&x| contents[x] & CANNOT_BE_LIGHT == 0).cloned().collect(),
+            None => Vec::new(),
+        };
+        if contents[loc] & CANNOT_BE_LIGHT == 0 {
+            sources.push(loc);
+        }
+        if !sources.is_empty() && best.as_ref().is_none_or(|b| sources.len() < b.len()) {
+            best = Some(sources);
+        }
+    }
+    best.map(|b| b[0])
+}
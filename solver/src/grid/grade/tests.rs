@@ -0,0 +1,132 @@
+use super::*;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+#[test]
+fn test_grade_easy() {
+    let test_grid_str =
+        "___
+         _4_
+         ___";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 3, 3).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert!(grade.solved_by_rules);
+    assert_eq!(Difficulty::Easy, grade.difficulty);
+    assert_eq!(0, grade.search_depth);
+    assert_eq!(Some(&1), grade.rule_counts.get(&Rule::ClueFilled));
+    assert_eq!(1, grade.rounds);
+}
+
+#[test]
+fn test_grade_medium() {
+    let test_grid_str =
+        "____0
+         X1__X
+         XX__X
+         1__21
+         _X___";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert!(grade.solved_by_rules);
+    assert_eq!(Difficulty::Medium, grade.difficulty);
+    assert!(grade.rule_counts.contains_key(&Rule::ClueCorner));
+}
+
+#[test]
+fn test_grade_hard() {
+    let test_grid_str =
+        "__1_1__
+         _______
+         X_____2
+         _______
+         0_____0
+         _______
+         __1_0__";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 7, 7).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert!(!grade.solved_by_rules);
+    assert!(grade.solved_by_lookahead);
+    assert_eq!(Difficulty::Hard, grade.difficulty);
+    assert!(grade.rule_counts.contains_key(&Rule::Lookahead));
+}
+
+#[test]
+fn test_grade_ambiguous() {
+    let test_grid_str =
+        "__
+         __";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 2, 2).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert!(!grade.solved_by_lookahead);
+    assert_eq!(Difficulty::Expert, grade.difficulty);
+    assert_eq!(1, grade.search_depth);
+    assert!(grade.rule_counts.is_empty());
+}
+
+#[test]
+fn test_grade_expert() {
+    let test_grid_str =
+        "__1__X__1_
+         0_____1___
+         _____X___0
+         _1________
+         2_X_______
+         _______1_1
+         ________2_
+         1___X_____
+         ___X_____X
+         _1__0__1__";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 10, 10).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert!(!grade.solved_by_rules);
+    assert_eq!(Difficulty::Expert, grade.difficulty);
+    assert!(grade.search_depth > 1);
+}
+
+#[test]
+fn test_grade_does_not_modify_input() {
+    let test_grid_str =
+        "___
+         _4_
+         ___";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 3, 3).unwrap());
+    let before = grid.grid.contents.clone();
+    grade_puzzle(&grid).unwrap();
+    assert_eq!(before, grid.grid.contents);
+}
+
+#[test]
+fn test_grade_unsolvable() {
+    let grid = precompute_data(get_grid_from_string("_0_", 1, 3).unwrap());
+    assert!(grade_puzzle(&grid).is_err());
+}
+
+#[test]
+fn test_parse_difficulty() {
+    for &difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Expert].iter() {
+        assert_eq!(Ok(difficulty), difficulty.to_string().parse::<Difficulty>());
+    }
+    assert!("impossible".parse::<Difficulty>().is_err());
+}
+
+#[test]
+fn test_grade_search_depth_is_least_needed() {
+    // Guessing a light first leads three guesses deep, but one guess is
+    // enough when the square is left dark instead.
+    let test_grid_str =
+        "X_1_
+         _1__
+         _X__
+         ____";
+    let grid = precompute_data(get_grid_from_string(test_grid_str, 4, 4).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert_eq!(1, grade.search_depth);
+}
+
+#[test]
+fn test_grade_search_depth_is_capped() {
+    // An open grid needs a guess for every light, far more than the cap allows.
+    let grid = precompute_data(get_grid_from_string(&"_".repeat(144), 12, 12).unwrap());
+    let grade = grade_puzzle(&grid).unwrap();
+    assert_eq!(MAX_SEARCH_DEPTH, grade.search_depth);
+    assert_eq!(Difficulty::Expert, grade.difficulty);
+}
@@ -0,0 +1,376 @@
+use super::{Grid, SatSolver};
+use super::coords::Coord;
+use super::generate::{generate_puzzle, GenerateOptions, Rng};
+use super::json::Puzzle;
+use super::puzzlink::encode_url;
+use super::share_code::encode_share_code;
+use super::tatham::encode_game_id;
+use super::utils::precompute_data;
+use super::verify::{can_complete, verify_lights};
+
+use serde::Deserialize;
+use serde_json::{self, Value};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[cfg(test)]
+mod tests;
+
+/// The largest height or width a puzzle can be asked for.
+const MAX_SIZE: i32 = 30;
+/// The largest request body that is read.
+const MAX_BODY_LEN: usize = 1 << 20;
+
+/// A request as read off the connection. Only what the player needs is kept.
+#[derive(Clone, Debug, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn json(value: &Value) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            content_type: "application/json".to_string(),
+            body: serde_json::to_vec(value).unwrap(),
+        }
+    }
+
+    /// An error status, with the message as a JSON object {"error": message}.
+    pub fn error(status: u16, message: &str) -> HttpResponse {
+        HttpResponse { status, ..HttpResponse::json(&serde_json::json!({"error": message})) }
+    }
+
+    pub fn write_to<W: Write>(&self, mut output: W) -> io::Result<()> {
+        write!(output, "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+                        Connection: close\r\n\r\n",
+               self.status, reason_phrase(self.status), self.content_type, self.body.len())?;
+        output.write_all(&self.body)?;
+        output.flush()
+    }
+}
+
+/**
+ * Reads a request line, headers and, if there is a Content-Length header,
+ * a body. Returns an error if the request is malformed or the body is too
+ * large.
+ */
+pub fn read_request<R: BufRead>(input: &mut R) -> Result<HttpRequest, String> {
+    let mut line = String::new();
+    input.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => { return Err(format!("Malformed request line {:?}", line.trim_end())); }
+    };
+    let (path, query) = match target.find('?') {
+        Some(idx) => (&target[..idx], parse_query(&target[idx + 1..])),
+        None => (target, HashMap::new()),
+    };
+    let path = percent_decode(path, false);
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>()
+                    .map_err(|_| format!("Invalid Content-Length {}", value.trim()))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return Err(format!("Request body of {} bytes is too large", content_length));
+    }
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body).map_err(|e| e.to_string())?;
+    Ok(HttpRequest { method, path, query, body })
+}
+
+/// What the player sends to have its lights checked.
+#[derive(Deserialize)]
+struct CheckRequest {
+    id: usize,
+    lights: Vec<Coord>,
+}
+
+struct State {
+    puzzles: HashMap<usize, Grid>,
+    next_id: usize,
+    rng: Rng,
+}
+
+/**
+ * The player's server: hands out new puzzles, checks the lights placed in
+ * them, and serves the player's static files. Each puzzle handed out is
+ * kept by its id, and saved to the data directory if there is one, so that
+ * it can still be checked after a restart.
+ */
+pub struct App {
+    static_dir: PathBuf,
+    data_dir: Option<PathBuf>,
+    solver: SatSolver,
+    state: Mutex<State>,
+}
+
+impl App {
+    /**
+     * Makes the server, loading the puzzles saved in the data directory, if
+     * one is given. It is created if it doesn't exist yet.
+     */
+    pub fn new(static_dir: PathBuf, data_dir: Option<PathBuf>, seed: u64, solver: SatSolver)
+            -> Result<App, String> {
+        let puzzles = match data_dir {
+            Some(ref dir) => load_puzzles(dir)?,
+            None => HashMap::new(),
+        };
+        let next_id = puzzles.keys().max().map_or(0, |x| x + 1);
+        Ok(App {
+            static_dir,
+            data_dir,
+            solver,
+            state: Mutex::new(State { puzzles, next_id, rng: Rng::new(seed) }),
+        })
+    }
+
+    pub fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => self.static_file("index.html"),
+            ("GET", "/new_puzzle") => self.new_puzzle(request),
+            ("POST", "/check_puzzle") => self.check_puzzle(request),
+            ("GET", path) if path.starts_with("/static/") => self.static_file(&path["/static/".len()..]),
+            (_, "/new_puzzle") | (_, "/check_puzzle") => HttpResponse::error(405, "Method not allowed"),
+            _ => HttpResponse::error(404, "Not found"),
+        }
+    }
+
+    fn new_puzzle(&self, request: &HttpRequest) -> HttpResponse {
+        let defaults = GenerateOptions::default();
+        let size = |name: &str, default: i32| match request.query.get(name) {
+            None => Ok(default),
+            Some(x) => match x.parse::<i32>() {
+                Ok(x) if x > 0 && x <= MAX_SIZE => Ok(x),
+                _ => Err(format!("Invalid {} {}, expected 1 to {}", name, x, MAX_SIZE)),
+            },
+        };
+        let (height, width) = match (size("height", defaults.height), size("width", defaults.width)) {
+            (Ok(height), Ok(width)) => (height, width),
+            (Err(e), _) | (_, Err(e)) => { return HttpResponse::error(400, &e); }
+        };
+
+        // Generating takes a while, so it is done on a generator of its own
+        // rather than holding the lock.
+        let mut rng = Rng::new(self.state.lock().unwrap().rng.next_u64());
+        let options = GenerateOptions { height, width, ..defaults };
+        let (grid, ratio, minimal) = match generate_puzzle(&options, &mut rng, self.solver) {
+            Ok(x) => (x.puzzle, x.ratio, x.minimal),
+            Err(e) => { return HttpResponse::error(400, &e); }
+        };
+
+        let puzzle_id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.puzzles.insert(id, grid.clone());
+            id
+        };
+        if let Some(ref dir) = self.data_dir {
+            if let Err(e) = save_puzzle(dir, puzzle_id, &grid) {
+                return HttpResponse::error(500, &e);
+            }
+        }
+
+        let puzzle = Puzzle::from(grid.clone());
+        HttpResponse::json(&serde_json::json!({
+            "height": height,
+            "width": width,
+            "data": puzzle.rows.join("\n"),
+            "url": encode_url(&grid),
+            "game_id": encode_game_id(&grid),
+            "code": encode_share_code(&grid),
+            "ratio": ratio,
+            "minimal": minimal,
+            "puzzle_id": puzzle_id,
+        }))
+    }
+
+    /**
+     * Answers soln-complete if the lights solve the puzzle, soln-unknown if
+     * they can still be added to to solve it, and soln-incorrect otherwise.
+     * Any solution is accepted, not just the one the puzzle was made from.
+     */
+    fn check_puzzle(&self, request: &HttpRequest) -> HttpResponse {
+        let check = match serde_json::from_slice::<CheckRequest>(&request.body) {
+            Ok(x) => x,
+            Err(e) => { return HttpResponse::error(400, &e.to_string()); }
+        };
+        let grid = match self.state.lock().unwrap().puzzles.get(&check.id) {
+            Some(grid) => grid.clone(),
+            None => { return HttpResponse::error(404, &format!("No puzzle with id {}", check.id)); }
+        };
+        let grid = precompute_data(grid);
+        let result = match verify_lights(&grid, &check.lights) {
+            Ok(ref x) if x.solved => "soln-complete",
+            Ok(_) => match can_complete(&grid, &check.lights, self.solver) {
+                Ok(true) => "soln-unknown",
+                Ok(false) => "soln-incorrect",
+                Err(e) => { return HttpResponse::error(500, &e); }
+            },
+            Err(e) => { return HttpResponse::error(400, &e); }
+        };
+        HttpResponse::json(&serde_json::json!({"result": result}))
+    }
+
+    fn static_file(&self, name: &str) -> HttpResponse {
+        // Only plain file names are served, so nothing outside the directory
+        // can be reached.
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return HttpResponse::error(404, "Not found");
+        }
+        match fs::read(self.static_dir.join(name)) {
+            Ok(body) => HttpResponse { status: 200, content_type: content_type_for(name).to_string(), body },
+            Err(_) => HttpResponse::error(404, "Not found"),
+        }
+    }
+}
+
+/**
+ * Listens on the given address, answering each connection on a thread of
+ * its own. Only returns if the address can't be listened on.
+ */
+pub fn run(app: App, address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let app = Arc::new(app);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(x) => x,
+            Err(_) => { continue; }
+        };
+        let app = Arc::clone(&app);
+        thread::spawn(move || handle_connection(&app, stream));
+    }
+    Ok(())
+}
+
+fn handle_connection(app: &App, stream: TcpStream) {
+    let mut reader = BufReader::new(&stream);
+    let response = match read_request(&mut reader) {
+        Ok(request) => app.handle(&request),
+        Err(e) => HttpResponse::error(400, &e),
+    };
+    // The client may have gone away, which is nothing to act on.
+    let _ = response.write_to(&stream);
+}
+
+fn load_puzzles(dir: &Path) -> Result<HashMap<usize, Grid>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut puzzles = HashMap::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("{}: {}", dir.display(), e))?.path();
+        let id = match (path.file_stem().and_then(|x| x.to_str()), path.extension()) {
+            (Some(stem), Some(ext)) if ext == "json" => match stem.parse::<usize>() {
+                Ok(id) => id,
+                Err(_) => { continue; }
+            },
+            _ => { continue; }
+        };
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let grid = serde_json::from_str::<Puzzle>(&text).map_err(|e| e.to_string())
+            .and_then(Grid::try_from)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        puzzles.insert(id, grid);
+    }
+    Ok(puzzles)
+}
+
+/// Writes the puzzle to a temporary file first, so a crash never leaves half of one.
+fn save_puzzle(dir: &Path, id: usize, grid: &Grid) -> Result<(), String> {
+    let path = dir.join(format!("{}.json", id));
+    let temp = dir.join(format!(".{}.json.tmp", id));
+    let text = serde_json::to_string(&Puzzle::from(grid.clone())).unwrap();
+    fs::write(&temp, text).and_then(|_| fs::rename(&temp, &path))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&').filter(|x| !x.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (percent_decode(name, true), percent_decode(value, true)),
+            None => (percent_decode(pair, true), String::new()),
+        })
+        .collect()
+}
+
+/// Decodes %XX escapes and, for query strings only, + for a space.
+fn percent_decode(text: &str, plus_as_space: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' if plus_as_space => { decoded.push(b' '); },
+            b'%' if idx + 2 < bytes.len() => {
+                let hex = (hex_value(bytes[idx + 1]), hex_value(bytes[idx + 2]));
+                match hex {
+                    (Some(high), Some(low)) => { decoded.push(high << 4 | low); idx += 2; },
+                    _ => { decoded.push(b'%'); },
+                }
+            },
+            x => { decoded.push(x); },
+        }
+        idx += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|x| x as u8)
+}
+
+fn content_type_for(name: &str) -> &'static str {
+    match Path::new(name).extension().and_then(|x| x.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
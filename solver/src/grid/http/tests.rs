@@ -0,0 +1,182 @@
+use super::*;
+use super::super::cnf_format::CnfFormula;
+use super::super::dpll::solve;
+use super::super::utils::get_grid_from_string;
+
+use std::env;
+use std::process;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("akari-http-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn get(path: &str, query: &[(&str, &str)]) -> HttpRequest {
+    HttpRequest {
+        method: "GET".to_string(),
+        path: path.to_string(),
+        query: query.iter().map(|&(x, y)| (x.to_string(), y.to_string())).collect(),
+        body: Vec::new(),
+    }
+}
+
+fn check(app: &App, id: usize, lights: &str) -> Value {
+    let request = HttpRequest {
+        method: "POST".to_string(),
+        path: "/check_puzzle".to_string(),
+        body: format!("{{\"id\": {}, \"lights\": {}}}", id, lights).into_bytes(),
+        ..HttpRequest::default()
+    };
+    let response = app.handle(&request);
+    assert_eq!(200, response.status);
+    serde_json::from_slice(&response.body).unwrap()
+}
+
+#[test]
+fn test_read_request() {
+    let input = "POST /check_puzzle?a=1&b=x+y%2Fz HTTP/1.1\r\nHost: localhost\r\n\
+                 content-length: 4\r\n\r\n{}\r\nextra";
+    let request = read_request(&mut input.as_bytes()).unwrap();
+    assert_eq!("POST", request.method);
+    assert_eq!("/check_puzzle", request.path);
+    assert_eq!("1", request.query["a"]);
+    assert_eq!("x y/z", request.query["b"]);
+    assert_eq!(b"{}\r\n".to_vec(), request.body);
+
+    // A + is only a space in the query string, not in the path.
+    let request = read_request(&mut "GET /a+b%20c.js?q=a+b HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+    assert_eq!("/a+b c.js", request.path);
+    assert_eq!("a b", request.query["q"]);
+
+    assert!(read_request(&mut "\r\n".as_bytes()).is_err());
+    assert!(read_request(&mut "GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n".as_bytes()).is_err());
+    assert!(read_request(&mut "GET / HTTP/1.1\r\nContent-Length: 9\r\n\r\nabc".as_bytes()).is_err());
+}
+
+#[test]
+fn test_write_response() {
+    let mut output = Vec::new();
+    HttpResponse::error(404, "Not found").write_to(&mut output).unwrap();
+    assert_eq!("HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 21\r\n\
+                Connection: close\r\n\r\n{\"error\":\"Not found\"}",
+               String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn test_new_puzzle() {
+    let app = App::new(temp_dir("static"), None, 1, solve).unwrap();
+    let response = app.handle(&get("/new_puzzle", &[("height", "4"), ("width", "6")]));
+    assert_eq!(200, response.status);
+    let puzzle: Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(4, puzzle["height"]);
+    assert_eq!(6, puzzle["width"]);
+    assert_eq!(0, puzzle["puzzle_id"]);
+    let rows: Vec<&str> = puzzle["data"].as_str().unwrap().split('\n').collect();
+    assert_eq!(4, rows.len());
+    assert!(rows.iter().all(|x| x.len() == 6));
+    assert!(puzzle["url"].as_str().unwrap().contains("lightup/6/4/"));
+    assert!(puzzle["game_id"].as_str().unwrap().starts_with("6x4:"));
+    assert!(puzzle["code"].is_string());
+    assert!(puzzle["ratio"].is_number());
+    assert_eq!(true, puzzle["minimal"]);
+
+    let response = app.handle(&get("/new_puzzle", &[]));
+    let puzzle: Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!((5, 5, 1), (puzzle["height"].as_i64().unwrap(), puzzle["width"].as_i64().unwrap(),
+                           puzzle["puzzle_id"].as_i64().unwrap()));
+
+    assert_eq!(400, app.handle(&get("/new_puzzle", &[("height", "0")])).status);
+    assert_eq!(400, app.handle(&get("/new_puzzle", &[("width", "abc")])).status);
+    assert_eq!(400, app.handle(&get("/new_puzzle", &[("width", "1000")])).status);
+}
+
+#[test]
+fn test_check_puzzle() {
+    let app = App::new(temp_dir("static"), None, 1, solve).unwrap();
+    app.state.lock().unwrap().puzzles.insert(3, get_grid_from_string("____3__X_", 3, 3).unwrap());
+    assert_eq!("soln-complete", check(&app, 3, "[[0, 1], [1, 0], [1, 2]]")["result"]);
+    assert_eq!("soln-unknown", check(&app, 3, "[[1, 0]]")["result"]);
+    assert_eq!("soln-unknown", check(&app, 3, "[]")["result"]);
+    assert_eq!("soln-incorrect", check(&app, 3, "[[0, 0]]")["result"]);
+
+    // Either of the two solutions of an open grid is accepted.
+    app.state.lock().unwrap().puzzles.insert(4, get_grid_from_string("____", 2, 2).unwrap());
+    assert_eq!("soln-complete", check(&app, 4, "[[0, 0], [1, 1]]")["result"]);
+    assert_eq!("soln-complete", check(&app, 4, "[[0, 1], [1, 0]]")["result"]);
+
+    let request = HttpRequest {
+        method: "POST".to_string(),
+        path: "/check_puzzle".to_string(),
+        body: b"{\"id\": 9, \"lights\": []}".to_vec(),
+        ..HttpRequest::default()
+    };
+    assert_eq!(404, app.handle(&request).status);
+    let on_wall = HttpRequest { body: b"{\"id\": 3, \"lights\": [[1, 1]]}".to_vec(), ..request.clone() };
+    assert_eq!(400, app.handle(&on_wall).status);
+    let outside = HttpRequest { body: b"{\"id\": 3, \"lights\": [[5, 5]]}".to_vec(), ..request.clone() };
+    assert_eq!(400, app.handle(&outside).status);
+    assert_eq!(400, app.handle(&HttpRequest { body: b"nope".to_vec(), ..request.clone() }).status);
+    assert_eq!(405, app.handle(&HttpRequest { method: "GET".to_string(), ..request }).status);
+}
+
+#[test]
+fn test_check_puzzle_solver_failure() {
+    fn broken(_: &CnfFormula) -> Result<Vec<i32>, String> {
+        Err("Error executing SAT solver".to_string())
+    }
+    let app = App::new(temp_dir("static-broken"), None, 1, broken).unwrap();
+    app.state.lock().unwrap().puzzles.insert(3, get_grid_from_string("____3__X_", 3, 3).unwrap());
+    let request = HttpRequest {
+        method: "POST".to_string(),
+        path: "/check_puzzle".to_string(),
+        body: b"{\"id\": 3, \"lights\": [[1, 0]]}".to_vec(),
+        ..HttpRequest::default()
+    };
+    let response = app.handle(&request);
+    assert_eq!(500, response.status);
+    assert_eq!("Error executing SAT solver",
+               serde_json::from_slice::<Value>(&response.body).unwrap()["error"]);
+}
+
+#[test]
+fn test_puzzles_are_persisted() {
+    let dir = temp_dir("data");
+    let app = App::new(temp_dir("static"), Some(dir.clone()), 2, solve).unwrap();
+    app.handle(&get("/new_puzzle", &[("height", "3"), ("width", "3")]));
+    app.handle(&get("/new_puzzle", &[("height", "3"), ("width", "3")]));
+    assert!(dir.join("0.json").is_file());
+    assert!(dir.join("1.json").is_file());
+    let saved = app.state.lock().unwrap().puzzles[&1].clone();
+
+    let restarted = App::new(temp_dir("static"), Some(dir.clone()), 2, solve).unwrap();
+    let state = restarted.state.lock().unwrap();
+    assert_eq!(2, state.next_id);
+    assert_eq!(saved.contents, state.puzzles[&1].contents);
+    drop(state);
+
+    fs::write(dir.join("2.json"), "{}").unwrap();
+    assert!(App::new(temp_dir("static"), Some(dir.clone()), 2, solve).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_static_files() {
+    let dir = temp_dir("files");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("index.html"), "<html></html>").unwrap();
+    fs::write(dir.join("main.css"), "body {}").unwrap();
+    let app = App::new(dir.clone(), None, 1, solve).unwrap();
+
+    let response = app.handle(&get("/", &[]));
+    assert_eq!((200, "text/html; charset=utf-8"), (response.status, response.content_type.as_str()));
+    assert_eq!(b"<html></html>".to_vec(), response.body);
+    let response = app.handle(&get("/static/main.css", &[]));
+    assert_eq!((200, "text/css"), (response.status, response.content_type.as_str()));
+
+    assert_eq!(404, app.handle(&get("/static/missing.js", &[])).status);
+    assert_eq!(404, app.handle(&get("/static/../Cargo.toml", &[])).status);
+    assert_eq!(404, app.handle(&get("/static/..", &[])).status);
+    assert_eq!(404, app.handle(&get("/elsewhere", &[])).status);
+    fs::remove_dir_all(&dir).unwrap();
+}
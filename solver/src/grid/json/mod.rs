@@ -0,0 +1,60 @@
+use super::Grid;
+use super::parser::{square_for, Dialect};
+use super::utils::print_grid_to_string;
+
+use serde::{Serialize, Deserialize};
+
+use std::convert::TryFrom;
+
+#[cfg(test)]
+mod tests;
+
+/**
+ * How a Grid looks in JSON, for instance
+ * {"height": 3, "width": 3, "rows": ["___", "_3_", "_X_"]}
+ * Each row is a string in the native notation, so it may contain lights
+ * and marks as well as walls and clues.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub height: i32,
+    pub width: i32,
+    pub rows: Vec<String>,
+}
+
+impl From<Grid> for Puzzle {
+    fn from(grid: Grid) -> Puzzle {
+        let squares: Vec<char> = print_grid_to_string(&grid, false).chars().collect();
+        Puzzle {
+            height: grid.height,
+            width: grid.width,
+            rows: squares.chunks(grid.width as usize).map(|x| x.iter().collect()).collect(),
+        }
+    }
+}
+
+impl TryFrom<Puzzle> for Grid {
+    type Error = String;
+
+    fn try_from(puzzle: Puzzle) -> Result<Grid, String> {
+        if puzzle.height <= 0 || puzzle.width <= 0 {
+            return Err(format!("Invalid dimensions {}x{}", puzzle.height, puzzle.width));
+        }
+        if puzzle.rows.len() != puzzle.height as usize {
+            return Err(format!("Expected {} rows, found {}", puzzle.height, puzzle.rows.len()));
+        }
+        let mut contents = Vec::with_capacity(puzzle.rows.len() * puzzle.width as usize);
+        for (row_idx, row) in puzzle.rows.iter().enumerate() {
+            if row.chars().count() != puzzle.width as usize {
+                return Err(format!("Row {} has {} squares, expected {}",
+                                   row_idx + 1, row.chars().count(), puzzle.width));
+            }
+            for (column, c) in row.chars().enumerate() {
+                contents.push(square_for(c, Dialect::Native).ok_or_else(|| {
+                    format!("Unexpected character '{}' in row {}, column {}", c, row_idx + 1, column + 1)
+                })?);
+            }
+        }
+        Ok(Grid { contents, height: puzzle.height, width: puzzle.width })
+    }
+}
@@ -0,0 +1,62 @@
+use super::*;
+use super::super::{GridData, solve_puzzle_with_stats};
+use super::super::dpll::solve;
+use super::super::grade::grade_puzzle;
+use super::super::rules::Rule;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+use serde_json;
+
+#[test]
+fn test_serialize_grid() {
+    let grid = get_grid_from_string("*_1X^3", 2, 3).unwrap();
+    assert_eq!(r#"{"height":2,"width":3,"rows":["*_1","X^3"]}"#,
+               serde_json::to_string(&grid).unwrap());
+}
+
+#[test]
+fn test_deserialize_grid() {
+    let grid: Grid = serde_json::from_str(
+        r#"{"height": 3, "width": 3, "rows": ["___", "_3_", "_X_"]}"#).unwrap();
+    assert_eq!("____3__X_", print_grid_to_string(&grid, false));
+    let grid: GridData = serde_json::from_str(
+        r#"{"height": 1, "width": 2, "rows": ["_1"]}"#).unwrap();
+    assert_eq!(Some(&vec![]), grid.sight_lines.get(&0));
+}
+
+#[test]
+fn test_deserialize_errors() {
+    let parse = |s: &str| serde_json::from_str::<Grid>(s).map(|_| ()).map_err(|e| e.to_string());
+    assert!(parse(r#"{"height": 0, "width": 1, "rows": []}"#).unwrap_err()
+            .contains("Invalid dimensions 0x1"));
+    assert!(parse(r#"{"height": 2, "width": 1, "rows": ["_"]}"#).unwrap_err()
+            .contains("Expected 2 rows, found 1"));
+    assert!(parse(r#"{"height": 1, "width": 2, "rows": ["___"]}"#).unwrap_err()
+            .contains("Row 1 has 3 squares, expected 2"));
+    assert!(parse(r#"{"height": 1, "width": 2, "rows": ["_Q"]}"#).unwrap_err()
+            .contains("Unexpected character 'Q' in row 1, column 2"));
+    assert!(parse(r#"{"height": 1, "width": 1}"#).is_err());
+}
+
+#[test]
+fn test_serialize_solution() {
+    let mut grid = precompute_data(get_grid_from_string("__\n__", 2, 2).unwrap());
+    let solution = solve_puzzle_with_stats(&mut grid, solve, 0).unwrap();
+    let value = serde_json::to_value(&solution).unwrap();
+    assert_eq!(2, value["height"]);
+    assert_eq!(false, value["unique"]);
+    assert_eq!(2, value["lights"].as_array().unwrap().len());
+    assert_eq!(2, value["lights"][0].as_array().unwrap().len());
+    assert_ne!(value["lights"], value["second_solution"]);
+    assert_eq!(2, value["stats"]["solver_calls"]);
+    assert_eq!(false, value["stats"]["solved_by_rules"]);
+}
+
+#[test]
+fn test_serialize_grade() {
+    let grid = precompute_data(get_grid_from_string("___\n_4_\n___", 3, 3).unwrap());
+    let value = serde_json::to_value(grade_puzzle(&grid).unwrap()).unwrap();
+    assert_eq!("easy", value["difficulty"]);
+    assert_eq!(1, value["rule_counts"]["clue_filled"]);
+    assert_eq!("\"clue_pair\"", serde_json::to_string(&Rule::CluePair).unwrap());
+}
@@ -1,9 +1,34 @@
+use serde::{Serialize, Deserialize};
+
 use std::collections::HashMap;
-use std::usize;
+use std::time::Instant;
 
 pub mod utils;
 pub mod rules;
 pub mod cnf_format;
+pub mod grade;
+pub mod dpll;
+pub mod certify;
+pub mod parser;
+pub mod puzzlink;
+pub mod pzprv3;
+pub mod tatham;
+pub mod share_code;
+pub mod json;
+pub mod svg;
+pub mod booklet;
+pub mod terminal;
+pub mod coords;
+pub mod batch;
+pub mod verify;
+pub mod serve;
+pub mod clues;
+pub mod backbone;
+pub mod generate;
+pub mod pbm;
+pub mod http;
+
+use self::coords::Coord;
 
 const IS_SOLID: u8 = 1<<3;
 const IS_LIT: u8 = 1<<4;
@@ -13,39 +38,159 @@ const IS_CONSTRAINED: u8 = 1<<7;
 
 const INVALID_POSITION: usize = usize::MAX;
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "json::Puzzle", try_from = "json::Puzzle")]
 pub struct Grid {
     contents: Vec<u8>,
     width: i32,
     height: i32,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "Grid", from = "Grid")]
 pub struct GridData {
     grid: Grid,
     sight_lines: HashMap<usize, Vec<usize>>
 }
 
+impl GridData {
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl From<Grid> for GridData {
+    fn from(grid: Grid) -> GridData {
+        utils::precompute_data(grid)
+    }
+}
+
+impl From<GridData> for Grid {
+    fn from(grid: GridData) -> Grid {
+        grid.grid
+    }
+}
+
+/// Everything solve_puzzle_with_stats found out about a puzzle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Solution {
+    pub height: i32,
+    pub width: i32,
+    /// The lights of a solution, in row-major order.
+    pub lights: Vec<Coord>,
+    pub unique: bool,
+    /// The lights of a different solution, if there is one.
+    pub second_solution: Option<Vec<Coord>>,
+    pub stats: SolveStats,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolveStats {
+    /// Whether the rules solved the puzzle without the SAT solver.
+    pub solved_by_rules: bool,
+    /// The number of rule applications that changed the grid.
+    pub deductions: usize,
+    /// The number of passes over the grid the rules made.
+    pub rounds: usize,
+    pub solver_calls: usize,
+    /// The size of the formula handed to the SAT solver, if it was needed.
+    pub variables: usize,
+    pub clauses: usize,
+    pub elapsed_ms: u64,
+}
+
 pub type SatSolver = fn(&cnf_format::CnfFormula) -> Result<Vec<i32>, String>;
 
+/// The error a SatSolver returns when the formula is unsatisfiable.
+pub const UNSAT: &str = "UNSAT";
+
+/**
+ * Calls the solver on the formula, and returns None if the formula is
+ * unsatisfiable. Any other error, such as the solver failing to run, is
+ * passed on rather than being taken to mean there is no solution.
+ */
+pub fn try_solve(solver: SatSolver, formula: &cnf_format::CnfFormula) -> Result<Option<Vec<i32>>, String> {
+    match solver(formula) {
+        Ok(x) => Ok(Some(x)),
+        Err(ref e) if e == UNSAT => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 /**
  * Solves the given puzzle, possibly mutating it in the process.
- * Returns a pair, where the first entry is a Vec of light locations,
- * and the second denotes whether the solution is unique.
+ * Returns a pair, where the first entry is a Vec of the positions of the
+ * lights in row-major order, and the second denotes whether the solution is
+ * unique.
+ */
+pub fn solve_puzzle(grid: &mut GridData, solver: SatSolver) -> Result<(Vec<Coord>, bool), String> {
+    solve_puzzle_with_lookahead(grid, solver, 0)
+}
+
+/**
+ * Same as solve_puzzle, but applies the rules with the given depth of lookahead
+ * before handing the grid to the SAT solver. If that solves the puzzle, the SAT
+ * solver is not called, since the rules only make forced deductions.
+ */
+pub fn solve_puzzle_with_lookahead(grid: &mut GridData, solver: SatSolver, depth: usize)
+        -> Result<(Vec<Coord>, bool), String> {
+    let solution = solve_puzzle_with_stats(grid, solver, depth)?;
+    Ok((solution.lights, solution.unique))
+}
+
+/**
+ * Same as solve_puzzle_with_lookahead, but reports the lights by row and
+ * column, a second solution if the puzzle has one, and how the solve went.
+ * The solution is only unique if the solver finds the formula without the
+ * first solution unsatisfiable. Any other error from the solver is returned.
  */
-pub fn solve_puzzle(grid: &mut GridData, solver: SatSolver) -> Result<(Vec<usize>, bool), String> {
-    rules::populate_with_rules(grid);
-    let cnf_formula = cnf_format::make_cnf_formula(&grid);
-    let solver_result = try!(solver(&cnf_formula));
-    let cnf_formula_excluding_previous_soln =
-        cnf_formula.append_inverse(&solver_result);
-
-    let is_unique = match solver(&cnf_formula_excluding_previous_soln) {
-        Ok(_) => false,
-        Err(_) => true
+pub fn solve_puzzle_with_stats(grid: &mut GridData, solver: SatSolver, depth: usize)
+        -> Result<Solution, String> {
+    let start = Instant::now();
+    let trace = rules::populate_with_lookahead_traced(grid, depth);
+    let mut stats = SolveStats {
+        solved_by_rules: rules::is_solved(grid),
+        deductions: trace.deductions.len(),
+        rounds: trace.rounds,
+        solver_calls: 0,
+        variables: 0,
+        clauses: 0,
+        elapsed_ms: 0,
     };
-    cnf_format::populate_grid_with_cnf(grid, &cnf_formula, solver_result);
+    let mut second_solution = None;
+    if !stats.solved_by_rules {
+        let cnf_formula = cnf_format::make_cnf_formula(grid);
+        stats.variables = cnf_formula.num_variables();
+        stats.clauses = cnf_formula.clauses().len();
+        let solver_result = solver(&cnf_formula)?;
+        let cnf_formula_excluding_previous_soln =
+            cnf_formula.append_inverse(&solver_result);
+        stats.solver_calls = 2;
+
+        if let Some(other_result) = try_solve(solver, &cnf_formula_excluding_previous_soln)? {
+            let mut other = grid.clone();
+            cnf_format::populate_grid_with_cnf(&mut other, &cnf_formula, other_result);
+            second_solution = Some(get_light_coordinates(&other));
+        }
+        cnf_format::populate_grid_with_cnf(grid, &cnf_formula, solver_result);
+    }
+    stats.elapsed_ms = start.elapsed().as_millis() as u64;
+    Ok(Solution {
+        height: grid.grid.height,
+        width: grid.grid.width,
+        lights: get_light_coordinates(grid),
+        unique: second_solution.is_none(),
+        second_solution,
+        stats,
+    })
+}
+
+fn get_light_coordinates(grid: &GridData) -> Vec<Coord> {
+    get_light_locations(grid).into_iter().map(|x| grid.grid.coord_of(x)).collect()
+}
 
-    let lit_locs = (0..((grid.grid.height * grid.grid.width) as usize))
+fn get_light_locations(grid: &GridData) -> Vec<usize> {
+    (0..((grid.grid.height * grid.grid.width) as usize))
         .filter(|&x| (grid.grid.contents[x] & IS_LIGHT) != 0)
-        .collect();
-    Ok((lit_locs, is_unique))
+        .collect()
 }
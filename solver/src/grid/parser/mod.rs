@@ -0,0 +1,219 @@
+use super::{IS_SOLID, IS_LIT, IS_LIGHT, CANT_LIGHT, IS_CONSTRAINED};
+use super::Grid;
+
+use serde::{Serialize, Deserialize};
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+const COMMENT_START: char = '%';
+
+/// The set of characters a puzzle is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dialect {
+    /// What the solver prints: `X` for walls, `_` for empty squares, and
+    /// `*`, `#` and `^` for lights, lit squares and squares that can't hold a light.
+    Native,
+    /// The notation used by most puzzle collections: `#` for walls and `.` or
+    /// `-` for empty squares. Only the puzzle itself can be written this way.
+    Plain,
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Dialect, String> {
+        match s {
+            "native" => Ok(Dialect::Native),
+            "plain" => Ok(Dialect::Plain),
+            _ => Err(format!("Unknown dialect {}, expected native or plain", s)),
+        }
+    }
+}
+
+/// An error in a puzzle description. Lines and columns are counted from 1.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: String) -> ParseError {
+        ParseError { line, column, message }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// A row of squares, along with where it came from.
+struct Row {
+    line: usize,
+    squares: Vec<(usize, u8)>,
+}
+
+/**
+ * Parses a puzzle description. Blank lines and lines starting with % are
+ * skipped. The first remaining line may give the height and width of the
+ * puzzle, separated by whitespace. Every other line is one row of squares,
+ * which may be indented but may not contain whitespace. With a header, the
+ * squares may instead all be on one line. Without a header, the dimensions
+ * are taken from the rows, which must all be the same length.
+ */
+pub fn parse_puzzle(input: &str, dialect: Dialect) -> Result<Grid, ParseError> {
+    let mut lines = input.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line))
+        .filter(|&(_, line)| !is_skipped(line))
+        .peekable();
+    let dims = match lines.peek() {
+        Some(&(num, line)) => parse_header(num, line)?,
+        None => { return Err(ParseError::new(1, 1, "No puzzle found".to_string())); }
+    };
+    let header_line = match dims {
+        Some(_) => lines.next().map_or(0, |(num, _)| num),
+        None => 0,
+    };
+    let rows = lines
+        .map(|(num, line)| parse_row(num, line, dialect))
+        .collect::<Result<Vec<_>, _>>()?;
+    if rows.is_empty() {
+        return Err(ParseError::new(header_line + 1, 1, "Expected a row of squares".to_string()));
+    }
+
+    let (height, width) = match dims {
+        Some(x) => x,
+        None => (rows.len() as i32, rows[0].squares.len() as i32),
+    };
+    let is_flat = dims.is_some() && rows.len() == 1
+        && rows[0].squares.len() as i32 == height * width;
+    if !is_flat {
+        check_layout(&rows, height, width)?;
+    }
+    let contents = rows.into_iter()
+        .flat_map(|r| r.squares.into_iter().map(|(_, square)| square))
+        .collect();
+    Ok(Grid { contents, height, width })
+}
+
+fn is_skipped(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with(COMMENT_START)
+}
+
+/// Returns the dimensions if the line is a header, and None if it isn't one.
+fn parse_header(num: usize, line: &str) -> Result<Option<(i32, i32)>, ParseError> {
+    let tokens = tokens_with_columns(line);
+    let is_number = |t: &str| {
+        let digits = t.strip_prefix('-').unwrap_or(t);
+        !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    if tokens.len() != 2 || !tokens.iter().all(|&(_, t)| is_number(t)) {
+        return Ok(None);
+    }
+
+    let mut dims = [0; 2];
+    for (idx, &(column, token)) in tokens.iter().enumerate() {
+        let name = if idx == 0 { "Height" } else { "Width" };
+        dims[idx] = match token.parse::<i32>() {
+            Ok(x) if x > 0 => x,
+            Ok(_) => {
+                return Err(ParseError::new(num, column, format!("{} must be positive", name)));
+            },
+            Err(_) => {
+                return Err(ParseError::new(num, column, format!("{} {} is too large", name, token)));
+            }
+        };
+    }
+    if dims[0].checked_mul(dims[1]).is_none() {
+        return Err(ParseError::new(num, 1, "Puzzle is too large".to_string()));
+    }
+    Ok(Some((dims[0], dims[1])))
+}
+
+/// Splits a line on whitespace, keeping the column each token starts at.
+pub(crate) fn tokens_with_columns(line: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut start = None;
+    for (column, (idx, c)) in line.char_indices().enumerate() {
+        match (start, c.is_whitespace()) {
+            (None, false) => { start = Some((column + 1, idx)); },
+            (Some((col, begin)), true) => {
+                result.push((col, &line[begin..idx]));
+                start = None;
+            },
+            _ => {}
+        }
+    }
+    if let Some((col, begin)) = start {
+        result.push((col, &line[begin..]));
+    }
+    result
+}
+
+fn parse_row(num: usize, line: &str, dialect: Dialect) -> Result<Row, ParseError> {
+    let end = line.trim_end().chars().count();
+    let mut squares = Vec::with_capacity(end);
+    for (column, c) in line.chars().enumerate().take(end).skip_while(|&(_, c)| c.is_whitespace()) {
+        if c.is_whitespace() {
+            return Err(ParseError::new(num, column + 1,
+                                       "Unexpected whitespace inside a row".to_string()));
+        }
+        match square_for(c, dialect) {
+            Some(square) => { squares.push((column + 1, square)); },
+            None => {
+                return Err(ParseError::new(num, column + 1,
+                                           format!("Unexpected character '{}'", c)));
+            }
+        }
+    }
+    Ok(Row { line: num, squares })
+}
+
+pub(crate) fn square_for(c: char, dialect: Dialect) -> Option<u8> {
+    match (c, dialect) {
+        ('_', _) | ('.', _) => Some(0),
+        ('X', _) | ('B', _) => Some(IS_SOLID),
+        ('-', Dialect::Plain) => Some(0),
+        ('#', Dialect::Plain) => Some(IS_SOLID),
+        ('#', Dialect::Native) => Some(IS_LIT),
+        ('*', Dialect::Native) => Some(IS_LIGHT),
+        ('^', Dialect::Native) => Some(CANT_LIGHT),
+        ('0'..='4', _) => c.to_digit(10).map(|x| x as u8 | IS_SOLID | IS_CONSTRAINED),
+        _ => None,
+    }
+}
+
+fn check_layout(rows: &[Row], height: i32, width: i32) -> Result<(), ParseError> {
+    for (idx, row) in rows.iter().enumerate() {
+        if idx as i32 >= height {
+            return Err(ParseError::new(row.line, row.squares[0].0,
+                                       format!("Expected {} rows, found more", height)));
+        }
+        let len = row.squares.len() as i32;
+        if len != width {
+            let column = if len > width {
+                row.squares[width as usize].0
+            } else {
+                row.squares[row.squares.len() - 1].0 + 1
+            };
+            return Err(ParseError::new(row.line, column,
+                                       format!("Row has {} squares, expected {}", len, width)));
+        }
+    }
+    if (rows.len() as i32) < height {
+        let last_line = rows[rows.len() - 1].line;
+        return Err(ParseError::new(last_line + 1, 1,
+                                   format!("Expected {} rows, found {}", height, rows.len())));
+    }
+    Ok(())
+}
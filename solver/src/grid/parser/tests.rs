@@ -0,0 +1,90 @@
+use super::*;
+use super::super::utils::print_grid_to_string;
+
+fn parse_error(line: usize, column: usize, message: &str) -> ParseError {
+    ParseError::new(line, column, message.to_string())
+}
+
+#[test]
+fn test_parse_with_header() {
+    let input = "3 4\nX_1_\n__*#\n4^__\n";
+    let grid = parse_puzzle(input, Dialect::Native).unwrap();
+    assert_eq!(3, grid.height);
+    assert_eq!(4, grid.width);
+    assert_eq!("X_1___*#4^__", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_parse_flat() {
+    let grid = parse_puzzle("2 3\n_X_1__", Dialect::Native).unwrap();
+    assert_eq!(2, grid.height);
+    assert_eq!(3, grid.width);
+    assert_eq!("_X_1__", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_infer_dimensions() {
+    let input =
+        "% A comment before the puzzle
+         __1
+
+         X._
+         % and one in the middle
+         _B2";
+    let grid = parse_puzzle(input, Dialect::Native).unwrap();
+    assert_eq!(3, grid.height);
+    assert_eq!(3, grid.width);
+    assert_eq!("__1X___X2", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_plain_dialect() {
+    let grid = parse_puzzle("#.-\n.B2", Dialect::Plain).unwrap();
+    assert_eq!("X___X2", print_grid_to_string(&grid, false));
+    assert_eq!(Err(parse_error(1, 2, "Unexpected character '*'")),
+               parse_puzzle(".*\n..", Dialect::Plain).map(|_| ()));
+}
+
+#[test]
+fn test_bad_character() {
+    assert_eq!(Err(parse_error(3, 5, "Unexpected character '5'")),
+               parse_puzzle("2 3\n___\n  X_5", Dialect::Native).map(|_| ()));
+}
+
+#[test]
+fn test_whitespace_inside_row() {
+    assert_eq!(Err(parse_error(1, 2, "Unexpected whitespace inside a row")),
+               parse_puzzle("_ _\n___", Dialect::Native).map(|_| ()));
+}
+
+#[test]
+fn test_row_lengths() {
+    assert_eq!(Err(parse_error(3, 3, "Row has 2 squares, expected 3")),
+               parse_puzzle("2 3\n___\n__", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(2, 4, "Row has 4 squares, expected 3")),
+               parse_puzzle("___\n____", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(4, 1, "Expected 3 rows, found 2")),
+               parse_puzzle("3 3\n___\n___\n", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(3, 1, "Expected 1 rows, found more")),
+               parse_puzzle("1 3\n___\n___\n", Dialect::Native).map(|_| ()));
+}
+
+#[test]
+fn test_bad_dimensions() {
+    assert_eq!(Err(parse_error(1, 1, "Height must be positive")),
+               parse_puzzle("-2 3\n______", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(1, 3, "Width must be positive")),
+               parse_puzzle("2 0\n", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(1, 1, "Puzzle is too large")),
+               parse_puzzle("100000 100000\n_", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(2, 1, "Expected a row of squares")),
+               parse_puzzle("2 2\n", Dialect::Native).map(|_| ()));
+    assert_eq!(Err(parse_error(1, 1, "No puzzle found")),
+               parse_puzzle("% nothing here\n", Dialect::Native).map(|_| ()));
+}
+
+#[test]
+fn test_dialect_from_str() {
+    assert_eq!(Ok(Dialect::Plain), "plain".parse::<Dialect>());
+    assert!("fancy".parse::<Dialect>().is_err());
+}
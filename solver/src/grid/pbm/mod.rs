@@ -0,0 +1,101 @@
+use super::IS_SOLID;
+use super::Grid;
+
+#[cfg(test)]
+mod tests;
+
+/// The largest number of squares a bitmap may have.
+const MAX_SQUARES: usize = 1 << 20;
+
+/// Whether the data starts like a PBM bitmap.
+pub fn is_pbm(data: &[u8]) -> bool {
+    data.starts_with(b"P1") || data.starts_with(b"P4")
+}
+
+/**
+ * Reads a wall layout from a PBM bitmap, in either the plain (P1) or the
+ * raw (P4) form, where each black pixel is a wall and each white pixel an
+ * empty square. The header is the magic number, the width and the height,
+ * separated by whitespace, with # starting a comment that runs to the end
+ * of the line. Anything after the last pixel is ignored.
+ */
+pub fn decode_pbm(data: &[u8]) -> Result<Grid, String> {
+    let mut reader = Reader { data, pos: 0 };
+    let raw = match reader.token() {
+        Some(b"P1") => false,
+        Some(b"P4") => true,
+        _ => { return Err("Not a PBM bitmap".to_string()); }
+    };
+    let width = reader.dimension("width")?;
+    let height = reader.dimension("height")?;
+    let size = (width as usize).checked_mul(height as usize).filter(|&x| x <= MAX_SQUARES)
+        .ok_or_else(|| format!("Bitmap of {}x{} pixels is too large", width, height))?;
+
+    let mut contents = Vec::with_capacity(size);
+    if raw {
+        // A single whitespace character separates the header from the pixels.
+        reader.pos += 1;
+        let row_len = (width as usize).div_ceil(8);
+        for row in 0..height as usize {
+            let start = reader.pos + row * row_len;
+            let bytes = data.get(start..start + row_len)
+                .ok_or_else(|| format!("Bitmap ends in row {} of {}", row + 1, height))?;
+            for col in 0..width as usize {
+                let black = bytes[col / 8] & (0x80 >> (col % 8)) != 0;
+                contents.push(if black { IS_SOLID } else { 0 });
+            }
+        }
+    } else {
+        while contents.len() < size {
+            reader.skip_whitespace();
+            match data.get(reader.pos) {
+                Some(b'1') => { contents.push(IS_SOLID); },
+                Some(b'0') => { contents.push(0); },
+                Some(&c) => { return Err(format!("Unexpected pixel '{}'", c as char)); },
+                None => {
+                    return Err(format!("Bitmap ends after {} of {} pixels", contents.len(), size));
+                }
+            }
+            reader.pos += 1;
+        }
+    }
+    Ok(Grid { contents, height, width })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Skips whitespace and comments.
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.data.get(self.pos) {
+            if c == b'#' {
+                while self.data.get(self.pos).is_some_and(|&x| x != b'\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|x| !x.is_ascii_whitespace() && *x != b'#') {
+            self.pos += 1;
+        }
+        if self.pos == start { None } else { Some(&self.data[start..self.pos]) }
+    }
+
+    fn dimension(&mut self, what: &str) -> Result<i32, String> {
+        let token = self.token().ok_or_else(|| format!("Expected the {}, found the end of the bitmap", what))?;
+        let text = String::from_utf8_lossy(token);
+        text.parse::<i32>().ok().filter(|&x| x > 0)
+            .ok_or_else(|| format!("Invalid {} {}", what, text))
+    }
+}
@@ -0,0 +1,33 @@
+use super::*;
+use super::super::utils::print_grid_to_string;
+
+#[test]
+fn test_decode_plain() {
+    let data = b"P1\n# A comment\n3 2\n1 0 1\n010\n";
+    assert!(is_pbm(data));
+    let grid = decode_pbm(data).unwrap();
+    assert_eq!((2, 3), (grid.height, grid.width));
+    assert_eq!("X_X_X_", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_raw() {
+    // Rows of 10 pixels take two bytes each.
+    let mut data = b"P4 10 2\n".to_vec();
+    data.extend_from_slice(&[0b1000_0001, 0b0100_0000, 0b0000_0000, 0b1111_1111]);
+    let grid = decode_pbm(&data).unwrap();
+    assert_eq!((2, 10), (grid.height, grid.width));
+    assert_eq!("X______X_X________XX", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_errors() {
+    assert!(!is_pbm(b"3 3\n___"));
+    assert_eq!("Not a PBM bitmap", decode_pbm(b"P2 1 1 0").err().unwrap());
+    assert_eq!("Invalid width x", decode_pbm(b"P1 x 1 0").err().unwrap());
+    assert_eq!("Expected the height, found the end of the bitmap", decode_pbm(b"P1 2").err().unwrap());
+    assert_eq!("Bitmap ends after 3 of 4 pixels", decode_pbm(b"P1 2 2 1 0 1").err().unwrap());
+    assert_eq!("Unexpected pixel '2'", decode_pbm(b"P1 1 1 2").err().unwrap());
+    assert_eq!("Bitmap ends in row 2 of 2", decode_pbm(b"P4 8 2\n\x01").err().unwrap());
+    assert!(decode_pbm(b"P1 100000 100000").is_err());
+}
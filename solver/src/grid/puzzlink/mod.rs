@@ -0,0 +1,132 @@
+use super::{IS_SOLID, IS_CONSTRAINED};
+use super::Grid;
+
+#[cfg(test)]
+mod tests;
+
+const URL_PREFIX: &str = "https://puzz.link/p?";
+const PUZZLE_NAMES: [&str; 2] = ["lightup", "akari"];
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+/// The longest run of empty squares a single character can stand for.
+const MAX_RUN: usize = 20;
+
+/// A square as puzz.link sees it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Square {
+    Empty,
+    Wall,
+    Clue(u8),
+}
+
+/**
+ * Reads a puzzle from a puzz.link or pzprv3 URL, such as
+ * https://puzz.link/p?lightup/10/10/... The part after the ? is enough.
+ * Only the walls and clues are encoded in these URLs.
+ */
+pub fn decode_url(url: &str) -> Result<Grid, String> {
+    let query = url.rsplit('?').next().unwrap_or(url);
+    let parts: Vec<&str> = query.split('/').collect();
+    if parts.len() < 3 || !PUZZLE_NAMES.contains(&parts[0]) {
+        return Err(format!("Not a Light Up URL: {}", url));
+    }
+    if parts.len() > 4 {
+        return Err(format!("Unexpected data after the board: {}", parts[4..].join("/")));
+    }
+    let width = parse_dimension(parts[1], "width")?;
+    let height = parse_dimension(parts[2], "height")?;
+    let size = width.checked_mul(height).ok_or("Puzzle is too large")? as usize;
+    let body = parts.get(3).cloned().unwrap_or("");
+
+    let mut squares = vec![Square::Empty; size];
+    let mut loc = 0;
+    for (idx, c) in body.char_indices() {
+        if loc >= size {
+            return Err(format!("Unexpected data after the board: {}", &body[idx..]));
+        }
+        let value = c.to_digit(36);
+        loc += match (c, value) {
+            ('.', _) => {
+                squares[loc] = Square::Wall;
+                1
+            },
+            (_, Some(x @ 0..=14)) if c.is_ascii_digit() || c.is_ascii_lowercase() => {
+                squares[loc] = Square::Clue((x % 5) as u8);
+                (x / 5) as usize + 1
+            },
+            (_, Some(x @ 16..=35)) if c.is_ascii_lowercase() => x as usize - 15,
+            _ => { return Err(format!("Unexpected character '{}' in URL", c)); }
+        };
+    }
+
+    let contents = squares.into_iter().map(|square| match square {
+        Square::Empty => 0,
+        Square::Wall => IS_SOLID,
+        Square::Clue(x) => x | IS_SOLID | IS_CONSTRAINED,
+    }).collect();
+    Ok(Grid { contents, height, width })
+}
+
+/// Writes the walls and clues of a puzzle as a puzz.link URL.
+pub fn encode_url(grid: &Grid) -> String {
+    let squares: Vec<Square> = grid.contents.iter().map(|&x| {
+        if x & IS_CONSTRAINED != 0 {
+            Square::Clue(x & CONSTRAINT_NUM_MASK)
+        } else if x & IS_SOLID != 0 {
+            Square::Wall
+        } else {
+            Square::Empty
+        }
+    }).collect();
+
+    let mut body = String::new();
+    let mut run = 0;
+    let mut loc = 0;
+    while loc < squares.len() {
+        let token = match squares[loc] {
+            Square::Empty => {
+                run += 1;
+                if run == MAX_RUN {
+                    body.push(run_char(run));
+                    run = 0;
+                }
+                loc += 1;
+                continue;
+            },
+            Square::Wall => ".".to_string(),
+            Square::Clue(x) => {
+                // A clue can absorb up to two empty squares after it.
+                let skipped = (1..3)
+                    .take_while(|&k| squares.get(loc + k) == Some(&Square::Empty)
+                                || loc + k >= squares.len())
+                    .count();
+                loc += skipped;
+                digit_char(x as u32 + 5 * skipped as u32).to_string()
+            }
+        };
+        if run > 0 {
+            body.push(run_char(run));
+            run = 0;
+        }
+        body.push_str(&token);
+        loc += 1;
+    }
+    if run > 0 {
+        body.push(run_char(run));
+    }
+    format!("{}{}/{}/{}/{}", URL_PREFIX, PUZZLE_NAMES[0], grid.width, grid.height, body)
+}
+
+fn parse_dimension(s: &str, name: &str) -> Result<i32, String> {
+    match s.parse::<i32>() {
+        Ok(x) if x > 0 => Ok(x),
+        _ => Err(format!("Invalid {} in URL: {}", name, s)),
+    }
+}
+
+fn run_char(run: usize) -> char {
+    digit_char(run as u32 + 15)
+}
+
+fn digit_char(x: u32) -> char {
+    std::char::from_digit(x, 36).expect("digit out of range")
+}
@@ -0,0 +1,81 @@
+use super::*;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+#[test]
+fn test_decode_url() {
+    let grid = decode_url("https://puzz.link/p?lightup/3/3/jd.g").unwrap();
+    assert_eq!(3, grid.height);
+    assert_eq!(3, grid.width);
+    assert_eq!("____3__X_", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_other_hosts() {
+    let expected = "____3__X_";
+    let grid = decode_url("http://pzv.jp/p.html?akari/3/3/jd.g").unwrap();
+    assert_eq!(expected, print_grid_to_string(&grid, false));
+    let grid = decode_url("lightup/3/3/jd.g").unwrap();
+    assert_eq!(expected, print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_rectangular() {
+    // Width comes before height in the URL.
+    let grid = decode_url("lightup/4/2/1.60").unwrap();
+    assert_eq!(2, grid.height);
+    assert_eq!(4, grid.width);
+    assert_eq!("1X1_0___", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_encode_url() {
+    let grid = get_grid_from_string(
+        "___
+         _3_
+         _X_", 3, 3).unwrap();
+    assert_eq!("https://puzz.link/p?lightup/3/3/jd.g", encode_url(&grid));
+}
+
+#[test]
+fn test_encode_long_runs() {
+    let grid = get_grid_from_string(&format!("{}2{}", "_".repeat(25), "_".repeat(4)), 1, 30)
+        .unwrap();
+    let url = encode_url(&grid);
+    assert_eq!("https://puzz.link/p?lightup/30/1/zkch", url);
+    assert_eq!(print_grid_to_string(&grid, false),
+               print_grid_to_string(&decode_url(&url).unwrap(), false));
+}
+
+#[test]
+fn test_round_trip() {
+    let test_grid_str =
+        "__1__X__1_
+         0_____1___
+         _____X___0
+         _1________
+         2_X_______
+         _______1_1
+         ________2_
+         1___X_____
+         ___X_____X
+         _1__0__1__";
+    let grid = get_grid_from_string(test_grid_str, 10, 10).unwrap();
+    let decoded = decode_url(&encode_url(&grid)).unwrap();
+    assert_eq!(print_grid_to_string(&grid, false), print_grid_to_string(&decoded, false));
+}
+
+#[test]
+fn test_encode_ignores_marks() {
+    let grid = get_grid_from_string("*#^X", 1, 4).unwrap();
+    assert_eq!("https://puzz.link/p?lightup/4/1/i.", encode_url(&grid));
+}
+
+#[test]
+fn test_decode_errors() {
+    assert!(decode_url("https://puzz.link/p?nurikabe/3/3/jd.g").is_err());
+    assert!(decode_url("lightup/0/3/").is_err());
+    assert!(decode_url("lightup/3/x/").is_err());
+    assert!(decode_url("lightup/3/3/jd.g.").is_err());
+    assert!(decode_url("lightup/3/3/jf").is_err());
+    assert!(decode_url("lightup/3/3/jd.g/extra").is_err());
+}
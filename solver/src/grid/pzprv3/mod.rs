@@ -0,0 +1,110 @@
+use super::{IS_SOLID, IS_LIGHT, CANT_LIGHT, IS_CONSTRAINED};
+use super::Grid;
+use super::parser::{tokens_with_columns, ParseError};
+
+#[cfg(test)]
+mod tests;
+
+const FILE_VERSIONS: [&str; 2] = ["pzprv3", "pzprv3.1"];
+const PUZZLE_NAMES: [&str; 2] = ["lightup", "akari"];
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+
+/**
+ * Reads a puzzle from a pzprv3 file, as saved by pzpr.js. The file starts
+ * with the version, the puzzle name, the number of rows and the number of
+ * columns, each on its own line, followed by one line per row. For Light Up,
+ * the board and the answer share a single layer: a digit is a clue, - is a
+ * wall, # is a light, + is a square marked as unable to hold a light, and
+ * . is an empty square. Anything after the last row is ignored.
+ */
+pub fn decode_file(input: &str) -> Result<Grid, ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let line_at = |idx: usize, what: &str| {
+        lines.get(idx).cloned().ok_or_else(|| {
+            ParseError::new(idx + 1, 1, format!("Expected {}, found the end of the file", what))
+        })
+    };
+
+    let version = line_at(0, "the file version")?.trim();
+    if !FILE_VERSIONS.contains(&version) {
+        return Err(ParseError::new(1, 1, format!("Not a pzprv3 file: {}", version)));
+    }
+    let name = line_at(1, "the puzzle name")?.trim();
+    if !PUZZLE_NAMES.contains(&name) {
+        return Err(ParseError::new(2, 1, format!("Not a Light Up puzzle: {}", name)));
+    }
+    let height = parse_dimension(line_at(2, "the number of rows")?.trim(), 3)?;
+    let width = parse_dimension(line_at(3, "the number of columns")?.trim(), 4)?;
+    if height.checked_mul(width).is_none() {
+        return Err(ParseError::new(3, 1, "Puzzle is too large".to_string()));
+    }
+
+    let mut contents = Vec::with_capacity((height * width) as usize);
+    for idx in 4..(4 + height as usize) {
+        let row = line_at(idx, "a row of squares")?.trim_end();
+        let tokens = tokens_with_columns(row);
+        if tokens.len() != width as usize {
+            let column = tokens.get(width as usize).map_or(row.chars().count() + 1, |t| t.0);
+            return Err(ParseError::new(idx + 1, column, format!(
+                "Row has {} squares, expected {}", tokens.len(), width)));
+        }
+        for (column, token) in tokens {
+            match square_for(token) {
+                Some(x) => { contents.push(x); },
+                None => {
+                    return Err(ParseError::new(idx + 1, column,
+                                               format!("Unexpected square '{}'", token)));
+                }
+            }
+        }
+    }
+    Ok(Grid { contents, height, width })
+}
+
+/// Writes a puzzle and its lights and marks as a pzprv3 file.
+pub fn encode_file(grid: &Grid) -> String {
+    let mut result = format!("{}\n{}\n{}\n{}\n",
+                             FILE_VERSIONS[0], PUZZLE_NAMES[0], grid.height, grid.width);
+    for row in grid.contents.chunks(grid.width as usize) {
+        for &square in row {
+            result.push_str(token_for(square));
+            result.push(' ');
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn parse_dimension(line: &str, num: usize) -> Result<i32, ParseError> {
+    match line.parse::<i32>() {
+        Ok(x) if x > 0 => Ok(x),
+        _ => Err(ParseError::new(num, 1, format!("Invalid dimension {}", line))),
+    }
+}
+
+fn square_for(token: &str) -> Option<u8> {
+    match token {
+        "." => Some(0),
+        "-" => Some(IS_SOLID),
+        "#" => Some(IS_LIGHT),
+        "+" => Some(CANT_LIGHT),
+        "0" | "1" | "2" | "3" | "4" => token.parse::<u8>().ok()
+            .map(|x| x | IS_SOLID | IS_CONSTRAINED),
+        _ => None,
+    }
+}
+
+fn token_for(square: u8) -> &'static str {
+    const CLUES: [&str; 5] = ["0", "1", "2", "3", "4"];
+    if square & IS_CONSTRAINED != 0 {
+        CLUES[(square & CONSTRAINT_NUM_MASK) as usize]
+    } else if square & IS_SOLID != 0 {
+        "-"
+    } else if square & IS_LIGHT != 0 {
+        "#"
+    } else if square & CANT_LIGHT != 0 {
+        "+"
+    } else {
+        "."
+    }
+}
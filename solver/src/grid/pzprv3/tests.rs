@@ -0,0 +1,57 @@
+use super::*;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+static TEST_FILE: &str = "pzprv3
+lightup
+3
+4
+# . 1 - 
++ 3 # . 
+. . . 0 
+";
+
+#[test]
+fn test_decode_file() {
+    let grid = decode_file(TEST_FILE).unwrap();
+    assert_eq!(3, grid.height);
+    assert_eq!(4, grid.width);
+    assert_eq!("*_1X^3*____0", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_encode_file() {
+    let grid = get_grid_from_string("*_1X^3*____0", 3, 4).unwrap();
+    assert_eq!(TEST_FILE, encode_file(&grid));
+}
+
+#[test]
+fn test_encode_drops_lit_squares() {
+    let grid = get_grid_from_string("*##X", 1, 4).unwrap();
+    assert_eq!("pzprv3\nlightup\n1\n4\n# . . - \n", encode_file(&grid));
+}
+
+#[test]
+fn test_decode_with_trailer() {
+    let input = "pzprv3.1\r\nakari\r\n1\r\n2\r\n. 2 \r\nhistory:\r\n{}\r\n";
+    let grid = decode_file(input).unwrap();
+    assert_eq!("_2", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_errors() {
+    let error = |line, column, message: &str| Err(ParseError::new(line, column, message.to_string()));
+    assert_eq!(error(1, 1, "Not a pzprv3 file: pzprv2"),
+               decode_file("pzprv2\nlightup\n1\n1\n.\n").map(|_| ()));
+    assert_eq!(error(2, 1, "Not a Light Up puzzle: nurikabe"),
+               decode_file("pzprv3\nnurikabe\n1\n1\n.\n").map(|_| ()));
+    assert_eq!(error(4, 1, "Invalid dimension 0"),
+               decode_file("pzprv3\nlightup\n1\n0\n").map(|_| ()));
+    assert_eq!(error(6, 1, "Expected a row of squares, found the end of the file"),
+               decode_file("pzprv3\nlightup\n2\n2\n. . \n").map(|_| ()));
+    assert_eq!(error(5, 5, "Row has 3 squares, expected 2"),
+               decode_file("pzprv3\nlightup\n1\n2\n. . . \n").map(|_| ()));
+    assert_eq!(error(5, 4, "Row has 1 squares, expected 2"),
+               decode_file("pzprv3\nlightup\n1\n2\n  . \n").map(|_| ()));
+    assert_eq!(error(5, 3, "Unexpected square '5'"),
+               decode_file("pzprv3\nlightup\n1\n2\n. 5 \n").map(|_| ()));
+}
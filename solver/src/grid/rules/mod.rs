@@ -1,7 +1,13 @@
 use super::{IS_SOLID, IS_LIT, IS_LIGHT, CANT_LIGHT, IS_CONSTRAINED};
 use super::{GridData};
+use super::coords::Coord;
 use super::utils::*;
 
+use serde::{Serialize, Deserialize};
+
+use std::collections::BTreeSet;
+use std::fmt;
+
 #[cfg(test)]
 mod tests;
 
@@ -31,13 +37,305 @@ static CORNER_RULE_LUT_3: [([bool; 4], [u8; 4]); 1] = [
     ([true, true, true, true], [4, 5, 6, 7])
 ];
 
+/// The individual deductions that `populate_with_rules` knows how to make.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// A clue already has all of its lights, so its other neighbors are dark.
+    ClueSatisfied,
+    /// A clue has exactly as many open neighbors as missing lights.
+    ClueFilled,
+    /// A clue with one spare neighbor rules out the diagonals it shares.
+    ClueCorner,
+    /// An empty square that no other square can light must hold a light.
+    LoneSquare,
+    /// A square that can't hold a light can only be lit from one place.
+    SingleSource,
+    /// Both remaining sources of a square would light a common diagonal.
+    SightCorner,
+    /// Counting the lights two clues can place in the squares they share
+    /// settles the rest of their neighbors, as in 3-1 and 2-1 diagonals.
+    CluePair,
+    /// An unlit square can only be lit by neighbors of a clue that needs one
+    /// more light, so the clue's other neighbors are dark.
+    ClueSource,
+    /// Assuming the opposite state for a square led to a contradiction.
+    Lookahead,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Rule::ClueSatisfied => "clue satisfied",
+            Rule::ClueFilled => "clue filled",
+            Rule::ClueCorner => "clue corner",
+            Rule::LoneSquare => "lone square",
+            Rule::SingleSource => "single source",
+            Rule::SightCorner => "sight corner",
+            Rule::CluePair => "clue pair",
+            Rule::ClueSource => "clue source",
+            Rule::Lookahead => "lookahead",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The state a deduction gives to a square.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mark {
+    Light,
+    Lit,
+    CantLight,
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Mark::Light => "light",
+            Mark::Lit => "lit",
+            Mark::CantLight => "no light",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One rule application that changed the grid. `premises` holds the indices
+/// of the earlier deductions that this one relied on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Deduction {
+    pub rule: Rule,
+    pub loc: Coord,
+    pub changes: Vec<(Coord, Mark)>,
+    pub premises: Vec<usize>,
+}
+
+/// The record of every deduction made while populating a grid, in order.
+/// `rounds` counts the passes over the grid that changed something.
+pub struct RuleTrace {
+    pub deductions: Vec<Deduction>,
+    pub rounds: usize,
+    width: usize,
+    causes: Vec<Vec<usize>>,
+    reads: Vec<Vec<usize>>,
+    snapshot: Vec<u8>,
+}
+
+impl RuleTrace {
+    fn new(grid: &GridData) -> RuleTrace {
+        RuleTrace {
+            deductions: Vec::new(),
+            rounds: 0,
+            width: grid.grid.width as usize,
+            causes: vec![Vec::new(); grid.grid.contents.len()],
+            reads: Vec::new(),
+            snapshot: grid.grid.contents.clone(),
+        }
+    }
+
+    /**
+     * Returns the indices of the deductions needed to force the state of the
+     * given square, in the order they were made. Deductions that did not
+     * contribute are left out. Returns None if the rules never touched the
+     * square or it is outside the grid.
+     */
+    pub fn explain(&self, square: Coord) -> Option<Vec<usize>> {
+        if square.col >= self.width {
+            return None;
+        }
+        let root = match self.causes.get(square.to_index(self.width)).and_then(|c| c.first()) {
+            Some(&x) => x,
+            None => { return None; }
+        };
+        let mut needed = BTreeSet::new();
+        let mut to_visit = vec![root];
+        while let Some(id) = to_visit.pop() {
+            if needed.insert(id) {
+                to_visit.extend(self.deductions[id].premises.iter().cloned());
+            }
+        }
+        Some(needed.into_iter().collect())
+    }
+
+    /**
+     * Returns the given squares together with every square read by the
+     * deductions that led to their current state.
+     */
+    fn squares_behind(&self, squares: &[usize]) -> Vec<usize> {
+        let mut seen = BTreeSet::new();
+        let mut result: BTreeSet<usize> = squares.iter().cloned().collect();
+        let mut to_visit: Vec<usize> = squares.iter()
+            .filter_map(|&x| self.causes[x].first().cloned())
+            .collect();
+        while let Some(id) = to_visit.pop() {
+            if seen.insert(id) {
+                for &x in self.reads[id].iter() {
+                    result.insert(x);
+                    to_visit.extend(self.causes[x].first().cloned());
+                }
+            }
+        }
+        result.into_iter().collect()
+    }
+
+    /**
+     * Records the changes made by a rule application at loc that read the given
+     * squares and wrote to the squares in written, returning false if none of
+     * the changes were new. Each square read only depends on the deduction that
+     * first gave it its state, since a square that is lit or can't hold a light
+     * stays out of the rules' way no matter how it got there.
+     */
+    fn record(&mut self, grid: &GridData, rule: Rule, loc: usize, read: Vec<usize>,
+              written: &[usize]) -> bool {
+        let mut premises: Vec<usize> = read.iter()
+            .filter_map(|&x| self.causes[x].first().cloned())
+            .collect();
+        premises.sort();
+        premises.dedup();
+
+        // A light also lights its sight line, so those squares may have changed too.
+        let mut touched = BTreeSet::new();
+        for &x in written {
+            touched.insert(x);
+            if grid.grid.contents[x] & IS_LIGHT != 0 {
+                touched.extend(grid.sight_lines.get(&x).into_iter().flatten().cloned());
+            }
+        }
+
+        let id = self.deductions.len();
+        let mut changes = Vec::new();
+        for idx in touched {
+            let (old, val) = (self.snapshot[idx], grid.grid.contents[idx]);
+            let added = val & !old;
+            if added == 0 {
+                continue;
+            }
+            self.snapshot[idx] = val;
+            // Only record marks that tell us something new about an empty square.
+            let mark = if val & IS_SOLID != 0 || old & (IS_LIGHT | IS_LIT) != 0 {
+                None
+            } else if added & IS_LIGHT != 0 {
+                Some(Mark::Light)
+            } else if added & IS_LIT != 0 {
+                Some(Mark::Lit)
+            } else if old & CANT_LIGHT == 0 {
+                Some(Mark::CantLight)
+            } else {
+                None
+            };
+            if let Some(m) = mark {
+                changes.push((Coord::from_index(idx, self.width), m));
+                self.causes[idx].push(id);
+            }
+        }
+        if changes.is_empty() {
+            return false;
+        }
+        let loc = Coord::from_index(loc, self.width);
+        self.deductions.push(Deduction { rule, loc, changes, premises });
+        self.reads.push(read);
+        true
+    }
+}
+
+/// The neighbors of the clue at loc, whose state the clue rules depend on.
+fn clue_squares(grid: &GridData, loc: usize) -> Vec<usize> {
+    get_neighbors(grid, loc).1[..4].iter()
+        .filter(|&&x| x != super::INVALID_POSITION)
+        .cloned()
+        .collect()
+}
+
+/// The squares a clue rule at loc depends on. A satisfied clue only needs its lights.
+fn clue_rule_squares(grid: &GridData, rule: Rule, loc: usize) -> Vec<usize> {
+    let squares = clue_squares(grid, loc);
+    if rule != Rule::ClueSatisfied {
+        return squares;
+    }
+    squares.into_iter().filter(|&x| grid.grid.contents[x] & IS_LIGHT != 0).collect()
+}
+
+/// The square at loc and the squares it can see.
+fn sight_squares(grid: &GridData, loc: usize) -> Vec<usize> {
+    let mut squares = vec![loc];
+    if let Some(sl) = grid.sight_lines.get(&loc) {
+        squares.extend(sl.iter().cloned());
+    }
+    squares
+}
+
 pub fn populate_with_rules(grid: &mut GridData) {
+    populate_with_lookahead(grid, 0);
+}
+
+/**
+ * Same as populate_with_rules, but also returns every deduction that was made
+ * along with the earlier deductions it depended on.
+ */
+pub fn populate_with_rules_traced(grid: &mut GridData) -> RuleTrace {
+    populate_with_lookahead_traced(grid, 0)
+}
+
+/**
+ * Applies the rules, and whenever they get stuck, tries each open square both
+ * ways on a copy of the grid. If one of the two states leads to a contradiction,
+ * the square is given the other one and the rules are applied again.
+ * The copies are populated with lookahead of one less depth, so a depth of 0
+ * is the same as populate_with_rules.
+ */
+pub fn populate_with_lookahead(grid: &mut GridData, depth: usize) {
+    populate(grid, depth, None);
+}
+
+/// Same as populate_with_lookahead, but also returns the deductions that were made.
+pub fn populate_with_lookahead_traced(grid: &mut GridData, depth: usize) -> RuleTrace {
+    let mut trace = RuleTrace::new(grid);
+    populate(grid, depth, Some(&mut trace));
+    trace
+}
+
+/// Applies the rules, recording the deductions only if there is a trace to record them in.
+fn populate(grid: &mut GridData, depth: usize, mut trace: Option<&mut RuleTrace>) {
+    loop {
+        apply_basic_rules(grid, trace.as_deref_mut());
+        let has_deduced = apply_pattern_rules(grid, trace.as_deref_mut())
+            || (depth > 0 && apply_lookahead(grid, trace.as_deref_mut(), depth));
+        if !has_deduced {
+            break;
+        }
+        if let Some(t) = trace.as_deref_mut() {
+            t.rounds += 1;
+        }
+    }
+}
+
+fn apply_basic_rules(grid: &mut GridData, mut trace: Option<&mut RuleTrace>) {
+    let mut written = Vec::new();
     loop {
         let mut has_changed = false;
+        let mut has_deduced = false;
         for i in 0..((grid.grid.height * grid.grid.width) as usize) {
-            let has_changed_this_iter = 
-                apply_constraint_rule(grid, i) || apply_spatial_rule(grid, i);
-            has_changed |= has_changed_this_iter;
+            written.clear();
+            let applied = constraint_rule(grid, i, &mut written).map(|r| (r, true))
+                .or_else(|| spatial_rule(grid, i, &mut written).map(|r| (r, false)));
+            let (r, is_clue_rule) = match applied {
+                Some(x) => x,
+                None => { continue; }
+            };
+            has_changed = true;
+            if let Some(t) = trace.as_deref_mut() {
+                let read = if is_clue_rule {
+                    clue_rule_squares(grid, r, i)
+                } else {
+                    sight_squares(grid, i)
+                };
+                has_deduced |= t.record(grid, r, i, read, &written);
+            }
+        }
+        if let Some(t) = trace.as_deref_mut() {
+            if has_deduced {
+                t.rounds += 1;
+            }
         }
         if !has_changed {
             break;
@@ -45,40 +343,335 @@ pub fn populate_with_rules(grid: &mut GridData) {
     }
 }
 
+/**
+ * Makes one pass of the rules that combine several clues or squares. These are
+ * only tried once the basic rules are stuck, so that deductions are credited
+ * to the simplest rule that makes them. Returns whether anything was deduced.
+ */
+fn apply_pattern_rules(grid: &mut GridData, mut trace: Option<&mut RuleTrace>) -> bool {
+    let mut has_deduced = false;
+    let mut written = Vec::new();
+    for i in 0..((grid.grid.height * grid.grid.width) as usize) {
+        written.clear();
+        let applied = clue_pair_rule(grid, i, &mut written)
+            .or_else(|| clue_source_rule(grid, i, &mut written));
+        if let Some((r, read)) = applied {
+            has_deduced |= match trace.as_deref_mut() {
+                Some(t) => t.record(grid, r, i, read, &written),
+                None => true,
+            };
+        }
+    }
+    has_deduced
+}
+
+/// Makes the first deduction that lookahead finds, returning whether there was one.
+fn apply_lookahead(grid: &mut GridData, trace: Option<&mut RuleTrace>, depth: usize) -> bool {
+    let cannot_light = IS_SOLID | IS_LIT | CANT_LIGHT | IS_LIGHT;
+    if find_contradiction(grid).is_some() {
+        return false;
+    }
+    let traced = trace.is_some();
+    for loc in 0..grid.grid.contents.len() {
+        if grid.grid.contents[loc] & cannot_light != 0 {
+            continue;
+        }
+        let read = match refute_assumption(grid, loc, true, depth, traced) {
+            Some(read) => {
+                grid.grid.contents[loc] |= CANT_LIGHT;
+                read
+            },
+            None => match refute_assumption(grid, loc, false, depth, traced) {
+                Some(read) => {
+                    insert_light(grid, loc);
+                    read
+                },
+                None => { continue; }
+            },
+        };
+        return match trace {
+            Some(t) => t.record(grid, Rule::Lookahead, loc, read, &[loc]),
+            None => true,
+        };
+    }
+    false
+}
+
+/**
+ * Assumes a light (or no light) at loc on a copy of the grid and populates it.
+ * If that leads to a contradiction, returns the squares the contradiction
+ * depended on, so the deduction can be traced back to them. Those are only
+ * worked out when traced is set, and are left empty otherwise.
+ */
+fn refute_assumption(grid: &GridData, loc: usize, light: bool, depth: usize, traced: bool)
+        -> Option<Vec<usize>> {
+    let mut trial = grid.clone();
+    if light {
+        insert_light(&mut trial, loc);
+    } else {
+        trial.grid.contents[loc] |= CANT_LIGHT;
+    }
+    if !traced {
+        populate(&mut trial, depth - 1, None);
+        return find_contradiction(&trial).map(|_| Vec::new());
+    }
+    let trial_trace = populate_with_lookahead_traced(&mut trial, depth - 1);
+    let bad_loc = find_contradiction(&trial)?;
+    let bad_squares = if trial.grid.contents[bad_loc] & IS_CONSTRAINED != 0 {
+        clue_squares(&trial, bad_loc)
+    } else {
+        sight_squares(&trial, bad_loc)
+    };
+    Some(trial_trace.squares_behind(&bad_squares).into_iter()
+        .filter(|&x| x != loc)
+        .collect())
+}
+
+/**
+ * Checks whether the grid can no longer be completed: a clue has too many
+ * or too few possible lights, or an unlit square has nowhere left to be lit from.
+ */
+pub fn has_contradiction(grid: &GridData) -> bool {
+    find_contradiction(grid).is_some()
+}
+
+/// Returns the location of the first clue or square that makes the grid unsolvable.
+fn find_contradiction(grid: &GridData) -> Option<usize> {
+    let cannot_light = IS_SOLID | IS_LIT | CANT_LIGHT | IS_LIGHT;
+    for loc in 0..grid.grid.contents.len() {
+        let val = grid.grid.contents[loc];
+        if val & IS_CONSTRAINED != 0 {
+            let (open, positions) = get_neighbors(grid, loc);
+            let num_open = open[..4].iter().filter(|&&x| x).count() as u8;
+            let num_lights = count_surrounding_lights(&grid.grid.contents, &positions[..4]);
+            let num = val & 0x7;
+            if num_lights > num || num_lights + num_open < num {
+                return Some(loc);
+            }
+        } else if val & (IS_SOLID | IS_LIT) == 0 {
+            let can_self_light = val & cannot_light == 0;
+            let has_source = get_filtered_sight_line(grid, loc)
+                .is_some_and(|sl| !sl.is_empty());
+            if !can_self_light && !has_source {
+                return Some(loc);
+            }
+        }
+    }
+    None
+}
+
+/// Checks whether every square is lit and every clue has exactly its number of lights.
+pub fn is_solved(grid: &GridData) -> bool {
+    for loc in 0..grid.grid.contents.len() {
+        let val = grid.grid.contents[loc];
+        if val & IS_CONSTRAINED != 0 {
+            let positions = get_neighbors(grid, loc).1;
+            if count_surrounding_lights(&grid.grid.contents, &positions[..4]) != val & 0x7 {
+                return false;
+            }
+        } else if val & (IS_SOLID | IS_LIT) == 0 {
+            return false;
+        }
+    }
+    true
+}
+
 pub fn apply_constraint_rule(grid: &mut GridData, loc: usize) -> bool {
+    constraint_rule(grid, loc, &mut Vec::new()).is_some()
+}
+
+/// Applies the rule for the clue at loc, adding the squares it changes to written.
+fn constraint_rule(grid: &mut GridData, loc: usize, written: &mut Vec<usize>) -> Option<Rule> {
     if grid.grid.contents[loc] & IS_CONSTRAINED == 0 {
-        return false;
+        return None;
     }
 
     let (valid, positions) = get_neighbors(grid, loc);
     let (valid_4, positions_4) = (&valid[..4], &positions[..4]);
     let num_mask: u8 = 0x7;
     let num_valid: u8 = valid_4.iter().fold(0, |a, &i| if i {a + 1} else {a});
-    let effective_constraint_num = (grid.grid.contents[loc] & num_mask)
-        - count_surrounding_lights(&grid.grid.contents, &positions_4);
+    let effective_constraint_num = match (grid.grid.contents[loc] & num_mask)
+            .checked_sub(count_surrounding_lights(&grid.grid.contents, positions_4)) {
+        Some(x) => x,
+        None => { return None; }
+    };
 
     if effective_constraint_num == 0 {
-        return mark_rel_positions(grid, &[0, 1, 2, 3], &positions, CANT_LIGHT);
+        return mark_rel_positions(grid, &[0, 1, 2, 3], &positions, CANT_LIGHT, written)
+            .then_some(Rule::ClueSatisfied);
     } else if effective_constraint_num == num_valid {
-        return apply_number_light_rule(grid, valid_4, positions_4);
+        return apply_number_light_rule(grid, valid_4, positions_4, written)
+            .then_some(Rule::ClueFilled);
     } else if effective_constraint_num + 1 == num_valid {
-        return apply_number_corner_rule(grid, effective_constraint_num, &valid, &positions);
+        return apply_number_corner_rule(grid, effective_constraint_num, &valid, &positions, written)
+            .then_some(Rule::ClueCorner);
     }
-    false 
+    None
+}
+
+/// The number of lights the clue at loc still needs and its open neighbors.
+fn clue_needs(grid: &GridData, loc: usize) -> Option<(u8, Vec<usize>)> {
+    let (open, positions) = get_neighbors(grid, loc);
+    let num_lights = count_surrounding_lights(&grid.grid.contents, &positions[..4]);
+    let needed = (grid.grid.contents[loc] & 0x7).checked_sub(num_lights)?;
+    let candidates = (0..4).filter(|&i| open[i]).map(|i| positions[i]).collect();
+    Some((needed, candidates))
 }
 
-fn apply_number_light_rule(grid: &mut GridData, valid: &[bool], positions: &[usize]) -> bool {
+pub fn apply_clue_pair_rule(grid: &mut GridData, loc: usize) -> bool {
+    clue_pair_rule(grid, loc, &mut Vec::new()).is_some()
+}
+
+/**
+ * For the clue at loc and each other clue it shares open neighbors with, bounds
+ * how many lights can go into the shared squares. The bounds can force every
+ * shared square, or every unshared neighbor of one of the clues, one way.
+ */
+fn clue_pair_rule(grid: &mut GridData, loc: usize, written: &mut Vec<usize>)
+        -> Option<(Rule, Vec<usize>)> {
+    if grid.grid.contents[loc] & IS_CONSTRAINED == 0 {
+        return None;
+    }
+    let (needed, candidates) = clue_needs(grid, loc)?;
+    let mut partners: Vec<usize> = candidates.iter()
+        .flat_map(|&c| get_neighbors(grid, c).1[..4].to_vec())
+        .filter(|&x| x != super::INVALID_POSITION && x != loc
+                && grid.grid.contents[x] & IS_CONSTRAINED != 0)
+        .collect();
+    partners.sort();
+    partners.dedup();
+
+    for other in partners {
+        let (other_needed, other_candidates) = match clue_needs(grid, other) {
+            Some(x) => x,
+            None => { continue; }
+        };
+        let shared: Vec<usize> = candidates.iter().cloned()
+            .filter(|x| other_candidates.contains(x))
+            .collect();
+        let own: Vec<usize> = candidates.iter().cloned()
+            .filter(|x| !shared.contains(x))
+            .collect();
+        let others: Vec<usize> = other_candidates.iter().cloned()
+            .filter(|x| !shared.contains(x))
+            .collect();
+
+        let (a, b, num_shared) = (needed as usize, other_needed as usize, shared.len());
+        let low = a.saturating_sub(own.len()).max(b.saturating_sub(others.len()));
+        let high = num_shared.min(a).min(b);
+        if low > high {
+            continue;
+        }
+
+        let mut has_changed = false;
+        if high == 0 {
+            has_changed |= mark_squares(grid, &shared, CANT_LIGHT, written);
+        } else if low == num_shared {
+            has_changed |= light_squares(grid, &shared, written);
+        }
+        for &(n, outside) in [(a, &own), (b, &others)].iter() {
+            if n == low {
+                has_changed |= mark_squares(grid, outside, CANT_LIGHT, written);
+            } else if n - high == outside.len() {
+                has_changed |= light_squares(grid, outside, written);
+            }
+        }
+        if has_changed {
+            let mut read = clue_squares(grid, loc);
+            read.extend(clue_squares(grid, other));
+            return Some((Rule::CluePair, read));
+        }
+    }
+    None
+}
+
+pub fn apply_clue_source_rule(grid: &mut GridData, loc: usize) -> bool {
+    clue_source_rule(grid, loc, &mut Vec::new()).is_some()
+}
+
+/**
+ * If every square that can still light the unlit square at loc is a neighbor
+ * of one clue that needs exactly one more light, that light has to be one of
+ * them, so the rest of the clue's neighbors can't hold a light.
+ */
+fn clue_source_rule(grid: &mut GridData, loc: usize, written: &mut Vec<usize>)
+        -> Option<(Rule, Vec<usize>)> {
+    let cannot_light = IS_SOLID | IS_LIT | CANT_LIGHT | IS_LIGHT;
+    if grid.grid.contents[loc] & (IS_SOLID | IS_LIT) != 0 {
+        return None;
+    }
+    let mut sources = get_filtered_sight_line(grid, loc)?;
+    if grid.grid.contents[loc] & cannot_light == 0 {
+        sources.push(loc);
+    }
+    let first = *sources.first()?;
+
+    for &clue in get_neighbors(grid, first).1[..4].iter() {
+        if clue == super::INVALID_POSITION || grid.grid.contents[clue] & IS_CONSTRAINED == 0 {
+            continue;
+        }
+        let (needed, candidates) = match clue_needs(grid, clue) {
+            Some(x) => x,
+            None => { continue; }
+        };
+        if needed != 1 || !sources.iter().all(|x| candidates.contains(x)) {
+            continue;
+        }
+        let others: Vec<usize> = candidates.into_iter()
+            .filter(|x| !sources.contains(x))
+            .collect();
+        if mark_squares(grid, &others, CANT_LIGHT, written) {
+            let mut read = sight_squares(grid, loc);
+            read.extend(clue_squares(grid, clue));
+            return Some((Rule::ClueSource, read));
+        }
+    }
+    None
+}
+
+fn mark_squares(grid: &mut GridData, squares: &[usize], mark: u8,
+                written: &mut Vec<usize>) -> bool {
+    let mut has_changed = false;
+    for &x in squares.iter() {
+        if grid.grid.contents[x] & mark == 0 {
+            grid.grid.contents[x] |= mark;
+            written.push(x);
+            has_changed = true;
+        }
+    }
+    has_changed
+}
+
+fn light_squares(grid: &mut GridData, squares: &[usize], written: &mut Vec<usize>) -> bool {
+    let mut has_changed = false;
+    for &x in squares.iter() {
+        has_changed |= light_square(grid, x, written);
+    }
+    has_changed
+}
+
+/// Places a light at loc, adding it to written if it wasn't there already.
+fn light_square(grid: &mut GridData, loc: usize, written: &mut Vec<usize>) -> bool {
+    let has_changed = insert_light(grid, loc);
+    if has_changed {
+        written.push(loc);
+    }
+    has_changed
+}
+
+fn apply_number_light_rule(grid: &mut GridData, valid: &[bool], positions: &[usize],
+                           written: &mut Vec<usize>) -> bool {
     let mut has_changed = false;
     for (&should_consider, &position) in valid.iter().zip(positions.iter()) {
         if should_consider {
-            has_changed |= insert_light(grid, position);
+            has_changed |= light_square(grid, position, written);
         }
     }
     has_changed
 }
 
 fn mark_rel_positions(grid: &mut GridData, rel_positions: &[u8],
-                      abs_positions: &[usize], mark: u8) -> bool {
+                      abs_positions: &[usize], mark: u8, written: &mut Vec<usize>) -> bool {
     let mut has_changed = false;
     for &relpos in rel_positions.iter() {
         if relpos == INVALID_RELATIVE_POSITION {
@@ -91,6 +684,7 @@ fn mark_rel_positions(grid: &mut GridData, rel_positions: &[u8],
 
         if grid.grid.contents[ap] & mark == 0 {
             grid.grid.contents[ap] |= mark;
+            written.push(ap);
             has_changed = true;
         }
     }
@@ -98,11 +692,12 @@ fn mark_rel_positions(grid: &mut GridData, rel_positions: &[u8],
 }
 
 fn apply_number_corner_rule(grid: &mut GridData, effective_constraint_num: u8,
-                            valid: &[bool; 8], positions: &[usize; 8]) -> bool {
+                            valid: &[bool; 8], positions: &[usize; 8],
+                            written: &mut Vec<usize>) -> bool {
     let mut apply_corner_lut = |lut: &[([bool; 4], [u8; 4])]| -> bool {
         for &entry in lut.iter() {
-            if entry.0 == &valid[..4] {
-                return mark_rel_positions(grid, &entry.1, positions, CANT_LIGHT);
+            if entry.0 == valid[..4] {
+                return mark_rel_positions(grid, &entry.1, positions, CANT_LIGHT, written);
             }
         }
         false
@@ -116,7 +711,7 @@ fn apply_number_corner_rule(grid: &mut GridData, effective_constraint_num: u8,
     }
 }
 
-fn count_surrounding_lights(grid_contents: &Vec<u8>, adjacents: &[usize]) -> u8 {
+fn count_surrounding_lights(grid_contents: &[u8], adjacents: &[usize]) -> u8 {
     adjacents.iter().fold(0, |a, &i| {
         if i != super::INVALID_POSITION && grid_contents[i] & IS_LIGHT != 0 {
             a + 1
@@ -127,43 +722,46 @@ fn count_surrounding_lights(grid_contents: &Vec<u8>, adjacents: &[usize]) -> u8
 }
 
 pub fn apply_spatial_rule(grid: &mut GridData, loc: usize) -> bool {
+    spatial_rule(grid, loc, &mut Vec::new()).is_some()
+}
+
+/// Applies the rules for the empty square at loc, adding the squares they change to written.
+fn spatial_rule(grid: &mut GridData, loc: usize, written: &mut Vec<usize>) -> Option<Rule> {
     if grid.grid.contents[loc] & (IS_SOLID | IS_LIT | IS_LIGHT) != 0 {
-        return false;
+        return None;
     }
     let sl = match get_filtered_sight_line(grid, loc) {
         Some(x) => x,
-        None => { return false; }
+        None => { return None; }
     };
-    if sl.len() == 0 && grid.grid.contents[loc] & CANT_LIGHT == 0 {
-        return insert_light(grid, loc);
+    if sl.is_empty() && grid.grid.contents[loc] & CANT_LIGHT == 0 {
+        return light_square(grid, loc, written).then_some(Rule::LoneSquare);
     } else if sl.len() == 1 && grid.grid.contents[loc] & CANT_LIGHT != 0 {
-        return insert_light(grid, sl[0]);
+        return light_square(grid, sl[0], written).then_some(Rule::SingleSource);
     } else if sl.len() == 2 && grid.grid.contents[loc] & CANT_LIGHT != 0 {
         match compute_sight_corner_rule(grid, &sl, loc) {
             Some(x) => {
                 if grid.grid.contents[x] & CANT_LIGHT == 0 {
                     grid.grid.contents[x] |= CANT_LIGHT;
-                    return true;
+                    written.push(x);
+                    return Some(Rule::SightCorner);
                 }
             },
-            None => { return false; }
+            None => { return None; }
         }
     }
-    false
+    None
 }
 
 fn get_filtered_sight_line(grid: &GridData, loc: usize) -> Option<Vec<usize>> {
-    match grid.sight_lines.get(&loc) {
-        Some(sl) => 
-            Some(sl.into_iter()
-                .filter(|&&x| grid.grid.contents[x] & (CANT_LIGHT | IS_LIT) == 0)
-                .map(usize::clone)
-                .collect()),
-        None => None
-    }
+    grid.sight_lines.get(&loc).map(|sl|
+        sl.iter()
+            .filter(|&&x| grid.grid.contents[x] & (CANT_LIGHT | IS_LIT) == 0)
+            .cloned()
+            .collect())
 }
 
-fn compute_sight_corner_rule(grid: &GridData, valid_sl: &Vec<usize>, loc: usize) -> Option<usize> {
+fn compute_sight_corner_rule(grid: &GridData, valid_sl: &[usize], loc: usize) -> Option<usize> {
     if valid_sl.len() != 2 {
         return None;
     }
@@ -180,7 +778,7 @@ fn compute_sight_corner_rule(grid: &GridData, valid_sl: &Vec<usize>, loc: usize)
     };
     match (sight_of_potential_mark.binary_search(&valid_sl[0]),
         sight_of_potential_mark.binary_search(&valid_sl[1])) {
-        (Ok(_), Ok(_)) => { return Some(potential_mark); }
-        _ => { return None; }
+        (Ok(_), Ok(_)) => Some(potential_mark),
+        _ => None
     }
 }
@@ -5,7 +5,7 @@ use super::super::utils::{
     precompute_data
 };
 
-static TEST_GRID_STR: &'static str = 
+static TEST_GRID_STR: &str = 
 "____0
  X1__X
  XX__X
@@ -36,9 +36,9 @@ fn test_populate_with_rules_2() {
     let expected_result =
         "__X__
          _X*X_
-         __3_X
-         _1_^_
-         X___X".replace(char::is_whitespace, "");
+         #*3*X
+         ^1##*
+         X#*#X".replace(char::is_whitespace, "");
     let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
     populate_with_rules(&mut test_grid);
     println!("{}", print_grid_to_string(&test_grid.grid, true));
@@ -242,3 +242,244 @@ fn test_spatial_corner_rule_negative() {
 
     assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
 }
+
+#[test]
+fn test_traced_matches_untraced() {
+    let mut plain_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    let mut traced_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    populate_with_rules(&mut plain_grid);
+    let trace = populate_with_rules_traced(&mut traced_grid);
+    assert_eq!(print_grid_to_string(&plain_grid.grid, false),
+               print_grid_to_string(&traced_grid.grid, false));
+    assert!(trace.deductions.iter().all(|d| !d.changes.is_empty()));
+    assert_all_changes_recorded(&traced_grid, &trace);
+}
+
+/// Checks that every square the rules settled was given its state by some deduction.
+fn assert_all_changes_recorded(grid: &GridData, trace: &RuleTrace) {
+    let width = grid.grid.width as usize;
+    for (idx, &val) in grid.grid.contents.iter().enumerate() {
+        if val & IS_SOLID == 0 && val & (IS_LIT | CANT_LIGHT) != 0 {
+            assert!(trace.explain(Coord::from_index(idx, width)).is_some(), "square {}", idx);
+        }
+    }
+}
+
+#[test]
+fn test_explain_without_premises() {
+    let mut test_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    let trace = populate_with_rules_traced(&mut test_grid);
+    let chain = trace.explain(Coord::new(4, 0)).unwrap();
+    assert_eq!(1, chain.len());
+    let deduction = &trace.deductions[chain[0]];
+    assert_eq!(Rule::LoneSquare, deduction.rule);
+    assert_eq!(vec![(Coord::new(4, 0), Mark::Light)], deduction.changes);
+}
+
+#[test]
+fn test_explain_chain() {
+    let mut test_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    let trace = populate_with_rules_traced(&mut test_grid);
+    let rules: Vec<Rule> = trace.explain(Coord::new(0, 1)).unwrap().into_iter()
+        .map(|id| trace.deductions[id].rule)
+        .collect();
+    assert_eq!(vec![Rule::LoneSquare, Rule::ClueSatisfied, Rule::SingleSource, Rule::ClueFilled],
+               rules);
+}
+
+#[test]
+fn test_explain_leaves_out_unneeded_deductions() {
+    // The 0 clue rules out the square left of the 1 clue, which gets lit from
+    // below, but the 1 clue is satisfied by the light above it either way.
+    let test_grid_str =
+        "XX_XX
+         0_1__
+         X_XXX";
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 3, 5).unwrap());
+    let trace = populate_with_rules_traced(&mut test_grid);
+    let chain = trace.explain(Coord::new(1, 3)).unwrap();
+    let steps: Vec<(Rule, Coord)> = chain.iter()
+        .map(|&id| (trace.deductions[id].rule, trace.deductions[id].loc))
+        .collect();
+    assert_eq!(vec![(Rule::LoneSquare, Coord::new(0, 2)), (Rule::ClueSatisfied, Coord::new(1, 2))], steps);
+    assert!(trace.deductions.iter().any(|d| d.loc == Coord::new(1, 0) && d.rule == Rule::ClueSatisfied));
+}
+
+#[test]
+fn test_explain_untouched() {
+    let mut test_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    let trace = populate_with_rules_traced(&mut test_grid);
+    assert!(trace.explain(Coord::new(0, 4)).is_none());
+    assert!(trace.explain(Coord::new(4, 1)).is_none());
+    assert!(trace.explain(Coord::new(0, 5)).is_none());
+    assert!(trace.explain(Coord::new(5, 0)).is_none());
+}
+
+#[test]
+fn test_has_contradiction() {
+    let overfull_grid = precompute_data(get_grid_from_string("*1*", 1, 3).unwrap());
+    let unlightable_grid = precompute_data(get_grid_from_string("^X_", 1, 3).unwrap());
+    let fine_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    assert!(has_contradiction(&overfull_grid));
+    assert!(has_contradiction(&unlightable_grid));
+    assert!(!has_contradiction(&fine_grid));
+}
+
+#[test]
+fn test_is_solved() {
+    let mut test_grid = precompute_data(get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap());
+    assert!(!is_solved(&test_grid));
+    populate_with_rules(&mut test_grid);
+    assert!(is_solved(&test_grid));
+}
+
+static LOOKAHEAD_GRID_STR: &str =
+"__1_1__
+ _______
+ X_____2
+ _______
+ 0_____0
+ _______
+ __1_0__";
+
+#[test]
+fn test_lookahead_solves_stuck_grid() {
+    let mut rules_grid = precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    let mut lookahead_grid =
+        precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    populate_with_rules(&mut rules_grid);
+    populate_with_lookahead(&mut lookahead_grid, 1);
+    assert!(!is_solved(&rules_grid));
+    assert!(is_solved(&lookahead_grid));
+}
+
+#[test]
+fn test_lookahead_depth_0() {
+    let mut rules_grid = precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    let mut lookahead_grid =
+        precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    populate_with_rules(&mut rules_grid);
+    populate_with_lookahead(&mut lookahead_grid, 0);
+    assert_eq!(print_grid_to_string(&rules_grid.grid, false),
+               print_grid_to_string(&lookahead_grid.grid, false));
+}
+
+#[test]
+fn test_lookahead_trace() {
+    let mut test_grid = precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    let trace = populate_with_lookahead_traced(&mut test_grid, 1);
+    let mut plain_grid = precompute_data(get_grid_from_string(LOOKAHEAD_GRID_STR, 7, 7).unwrap());
+    populate_with_lookahead(&mut plain_grid, 1);
+    assert_eq!(print_grid_to_string(&plain_grid.grid, false),
+               print_grid_to_string(&test_grid.grid, false));
+    assert_all_changes_recorded(&test_grid, &trace);
+    let (id, lookahead) = trace.deductions.iter().enumerate()
+        .find(|&(_, d)| d.rule == Rule::Lookahead)
+        .unwrap();
+    assert!(!lookahead.premises.is_empty());
+    assert!(lookahead.premises.iter().all(|&p| p < id));
+    let (loc, _) = lookahead.changes[0];
+    assert_eq!(Some(&id), trace.explain(loc).unwrap().last());
+}
+
+#[test]
+fn test_clue_pair_rule_3_1() {
+    let test_grid_str =
+    "_____
+     __1__
+     _3___
+     _____
+     _____";
+    let expected_result =
+    "#_^__
+     #_1^_
+     *3___
+     #*###
+     ##___".replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    apply_clue_pair_rule(&mut test_grid, 11);
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
+
+#[test]
+fn test_clue_pair_rule_2_1_edge() {
+    let test_grid_str =
+    "_2___
+     __1__
+     _____";
+    let expected_result =
+    "*2___
+     #_1^_
+     #_^__".replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 3, 5).unwrap());
+    apply_clue_pair_rule(&mut test_grid, 1);
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
+
+#[test]
+fn test_clue_pair_rule_2_2_with_light() {
+    let test_grid_str =
+    "_2___
+     __2__
+     __*__";
+    let expected_result =
+    "*2___
+     #_2^_
+     #_*__".replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 3, 5).unwrap());
+    apply_clue_pair_rule(&mut test_grid, 1);
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
+
+#[test]
+fn test_clue_pair_rule_negative() {
+    let test_grid_str =
+    "_____
+     __2__
+     _2___
+     _____
+     _____";
+    let expected_result = test_grid_str.replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    assert!(!apply_clue_pair_rule(&mut test_grid, 11));
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
+
+#[test]
+fn test_clue_source_rule() {
+    let test_grid_str =
+    "___X_
+     _X_^X
+     __1__
+     ___X_
+     _____";
+    let expected_result =
+    "___X_
+     _X_^X
+     _^1__
+     __^X_
+     _____".replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    apply_clue_source_rule(&mut test_grid, 8);
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
+
+#[test]
+fn test_clue_source_rule_negative() {
+    let test_grid_str =
+    "___X_
+     _X___
+     __1__
+     ___X_
+     _____";
+    let expected_result = test_grid_str.replace(char::is_whitespace, "");
+    let mut test_grid = precompute_data(get_grid_from_string(test_grid_str, 5, 5).unwrap());
+    assert!(!apply_clue_source_rule(&mut test_grid, 8));
+
+    assert_eq!(&expected_result, &print_grid_to_string(&test_grid.grid, false));
+}
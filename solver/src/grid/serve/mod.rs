@@ -0,0 +1,157 @@
+use super::{Grid, SatSolver, Solution};
+use super::solve_puzzle_with_stats;
+use super::coords::Coord;
+use super::grade::grade_puzzle;
+use super::puzzlink::encode_url;
+use super::share_code::encode_share_code;
+use super::tatham::encode_game_id;
+use super::utils::{precompute_data, print_grid_to_string};
+use super::verify::verify_lights;
+
+use serde::{Serialize, Deserialize};
+use serde_json::{self, Value};
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+#[cfg(test)]
+mod tests;
+
+/// The most solutions kept around before the cache is cleared.
+const MAX_CACHED_SOLUTIONS: usize = 4096;
+
+/**
+ * A request to the server, as a line of JSON such as
+ * {"id": 1, "method": "unique", "puzzle": {"height": 1, "width": 2, "rows": ["_1"]}}
+ * The id can be any JSON value, and is sent back with the response.
+ */
+#[derive(Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub id: Value,
+    #[serde(flatten)]
+    pub method: Method,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Method {
+    /// Solves the puzzle, answering with the same object as --format json.
+    Solve {
+        puzzle: Grid,
+        #[serde(default)]
+        lookahead: usize,
+    },
+    /// Answers with {"unique": true} or {"unique": false}.
+    Unique {
+        puzzle: Grid,
+        #[serde(default)]
+        lookahead: usize,
+    },
+    /// Checks whether the lights solve the puzzle.
+    Verify {
+        puzzle: Grid,
+        lights: Vec<Coord>,
+    },
+    /// Grades the puzzle, answering with the same object as --grade.
+    Grade {
+        puzzle: Grid,
+    },
+    /// Answers with the puzz.link URL, Tatham game ID and share code of the puzzle.
+    Share {
+        puzzle: Grid,
+    },
+}
+
+/// The answer to a request, with either a result or an error.
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/**
+ * Answers requests one at a time, keeping the solutions it finds, so that
+ * asking about the same puzzle again doesn't call the SAT solver.
+ */
+pub struct Server {
+    solver: SatSolver,
+    solutions: HashMap<(String, usize), Solution>,
+}
+
+impl Server {
+    pub fn new(solver: SatSolver) -> Server {
+        Server { solver, solutions: HashMap::new() }
+    }
+
+    /**
+     * Reads requests from input, one per line, and writes a response to each
+     * to output on a line of its own, until the input ends. Blank lines are
+     * skipped.
+     */
+    pub fn serve<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(output, "{}", self.handle_line(&line))?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Answers a single request, given and returned as a line of JSON.
+    pub fn handle_line(&mut self, line: &str) -> String {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(value) => {
+                let id = value.get("id").cloned().unwrap_or(Value::Null);
+                match serde_json::from_value::<Request>(value) {
+                    Ok(request) => self.handle(request),
+                    Err(e) => Response { id, result: None, error: Some(e.to_string()) },
+                }
+            },
+            Err(e) => Response { id: Value::Null, result: None, error: Some(e.to_string()) },
+        };
+        serde_json::to_string(&response).unwrap()
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        let result = match request.method {
+            Method::Solve { puzzle, lookahead } => self.solve(puzzle, lookahead)
+                .map(|x| serde_json::to_value(x).unwrap()),
+            Method::Unique { puzzle, lookahead } => self.solve(puzzle, lookahead)
+                .map(|x| serde_json::json!({"unique": x.unique})),
+            Method::Verify { puzzle, lights } => verify_lights(&precompute_data(puzzle), &lights)
+                .map(|x| serde_json::to_value(x).unwrap()),
+            Method::Grade { puzzle } => grade_puzzle(&precompute_data(puzzle))
+                .map(|x| serde_json::to_value(x).unwrap()),
+            Method::Share { puzzle } => Ok(serde_json::json!({
+                "url": encode_url(&puzzle),
+                "game_id": encode_game_id(&puzzle),
+                "code": encode_share_code(&puzzle),
+            })),
+        };
+        match result {
+            Ok(x) => Response { id: request.id, result: Some(x), error: None },
+            Err(e) => Response { id: request.id, result: None, error: Some(e) },
+        }
+    }
+
+    fn solve(&mut self, puzzle: Grid, lookahead: usize) -> Result<Solution, String> {
+        let key = (format!("{}x{}:{}", puzzle.height, puzzle.width,
+                           print_grid_to_string(&puzzle, false)), lookahead);
+        if let Some(solution) = self.solutions.get(&key) {
+            return Ok(solution.clone());
+        }
+        let solution = solve_puzzle_with_stats(&mut precompute_data(puzzle), self.solver, lookahead)?;
+        if self.solutions.len() >= MAX_CACHED_SOLUTIONS {
+            self.solutions.clear();
+        }
+        self.solutions.insert(key, solution.clone());
+        Ok(solution)
+    }
+}
@@ -0,0 +1,116 @@
+use super::*;
+use super::super::cnf_format::CnfFormula;
+use super::super::dpll::solve;
+use super::super::utils::get_grid_from_string;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTED_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn counted_solve(cnf: &CnfFormula) -> Result<Vec<i32>, String> {
+    COUNTED_CALLS.fetch_add(1, Ordering::SeqCst);
+    solve(cnf)
+}
+
+fn respond(server: &mut Server, line: &str) -> Value {
+    serde_json::from_str(&server.handle_line(line)).unwrap()
+}
+
+#[test]
+fn test_solve_request() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, r#"{"id": 7, "method": "solve",
+        "puzzle": {"height": 3, "width": 3, "rows": ["___", "_3_", "_X_"]}}"#);
+    assert_eq!(7, response["id"]);
+    assert_eq!(serde_json::json!([[0, 1], [1, 0], [1, 2]]), response["result"]["lights"]);
+    assert_eq!(true, response["result"]["unique"]);
+    assert!(response.get("error").is_none());
+}
+
+#[test]
+fn test_unique_request() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, r#"{"id": "a", "method": "unique",
+        "puzzle": {"height": 2, "width": 2, "rows": ["__", "__"]}}"#);
+    assert_eq!(serde_json::json!({"id": "a", "result": {"unique": false}}), response);
+}
+
+#[test]
+fn test_verify_request() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, r#"{"id": 1, "method": "verify",
+        "puzzle": {"height": 1, "width": 3, "rows": ["_1_"]}, "lights": [[0, 0]]}"#);
+    assert_eq!(serde_json::json!({"solved": false, "conflicts": [], "wrong_clues": [],
+                                  "unlit": [[0, 2]]}), response["result"]);
+    let response = respond(&mut server, r#"{"id": 2, "method": "verify",
+        "puzzle": {"height": 1, "width": 3, "rows": ["_1_"]}, "lights": [[0, 1]]}"#);
+    assert_eq!("Light (0, 1) is on a wall", response["error"]);
+}
+
+#[test]
+fn test_grade_request() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, r#"{"id": null, "method": "grade",
+        "puzzle": {"height": 3, "width": 3, "rows": ["___", "_3_", "_X_"]}}"#);
+    assert_eq!("easy", response["result"]["difficulty"]);
+}
+
+#[test]
+fn test_share_request() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, r#"{"id": 3, "method": "share",
+        "puzzle": {"height": 3, "width": 3, "rows": ["___", "_3_", "_X_"]}}"#);
+    let grid = get_grid_from_string("____3__X_", 3, 3).unwrap();
+    assert_eq!(serde_json::json!({"url": encode_url(&grid), "game_id": encode_game_id(&grid),
+                                  "code": encode_share_code(&grid)}), response["result"]);
+}
+
+#[test]
+fn test_bad_requests() {
+    let mut server = Server::new(solve);
+    let response = respond(&mut server, "not json");
+    assert_eq!(Value::Null, response["id"]);
+    assert!(response["error"].is_string());
+
+    let response = respond(&mut server, r#"{"id": 3, "method": "dance"}"#);
+    assert_eq!(3, response["id"]);
+    assert!(response["error"].as_str().unwrap().contains("dance"));
+
+    let response = respond(&mut server, r#"{"id": 4, "method": "solve",
+        "puzzle": {"height": 1, "width": 2, "rows": ["_"]}}"#);
+    assert!(response["error"].as_str().unwrap().contains("Row 1 has 1 squares, expected 2"));
+
+    let response = respond(&mut server, r#"{"id": 5, "method": "solve",
+        "puzzle": {"height": 1, "width": 1, "rows": ["4"]}}"#);
+    assert_eq!(5, response["id"]);
+    assert!(response["error"].is_string());
+    assert!(response.get("result").is_none());
+}
+
+#[test]
+fn test_solutions_are_cached() {
+    let mut server = Server::new(counted_solve);
+    let request = r#"{"id": 1, "method": "unique",
+        "puzzle": {"height": 2, "width": 2, "rows": ["__", "__"]}}"#;
+    respond(&mut server, request);
+    assert_eq!(2, COUNTED_CALLS.load(Ordering::SeqCst));
+    respond(&mut server, request);
+    respond(&mut server, &request.replace("unique", "solve"));
+    assert_eq!(2, COUNTED_CALLS.load(Ordering::SeqCst));
+    respond(&mut server, &request.replace("\"unique\",", "\"unique\", \"lookahead\": 1,"));
+    assert_eq!(4, COUNTED_CALLS.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_serve() {
+    let input = "{\"id\": 1, \"method\": \"grade\", \"puzzle\": {\"height\": 1, \"width\": 1, \
+                 \"rows\": [\"_\"]}}\n\n{\"id\": 2}\n";
+    let mut output = Vec::new();
+    Server::new(solve).serve(input.as_bytes(), &mut output).unwrap();
+    let lines: Vec<Value> = String::from_utf8(output).unwrap().lines()
+        .map(|x| serde_json::from_str(x).unwrap()).collect();
+    assert_eq!(2, lines.len());
+    assert_eq!(1, lines[0]["id"]);
+    assert_eq!(2, lines[1]["id"]);
+    assert!(lines[1]["error"].is_string());
+}
@@ -0,0 +1,281 @@
+use super::{IS_SOLID, IS_CONSTRAINED};
+use super::Grid;
+
+#[cfg(test)]
+mod tests;
+
+const VERSION: u8 = 1;
+/// Set in the flags byte when the payload is run-length compressed.
+const FLAG_COMPRESSED: u8 = 1;
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+/// The clue bits of a wall without a clue.
+const NO_CLUE: u8 = 0x7;
+const CLUE_BITS: usize = 3;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/**
+ * Encodes the walls and clues of a puzzle as a short code that is safe to
+ * use in URLs. The code is URL-safe base64 without padding, of these bytes:
+ * the format version, a flags byte, the payload, and a CRC-16 of everything
+ * before it. The payload holds the height and width as LEB128 numbers, one
+ * bit per square that is set for walls, and three bits per wall holding its
+ * clue, or 7 for no clue, all packed from the high bit down. If it comes out
+ * shorter, the payload is run-length compressed and the flag is set.
+ */
+pub fn encode_share_code(grid: &Grid) -> String {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, grid.height as u32);
+    write_varint(&mut payload, grid.width as u32);
+    let mut bits = BitWriter::new(payload);
+    for &square in grid.contents.iter() {
+        bits.write(square & IS_SOLID != 0);
+    }
+    for &square in grid.contents.iter().filter(|&&x| x & IS_SOLID != 0) {
+        let clue = if square & IS_CONSTRAINED != 0 { square & CONSTRAINT_NUM_MASK } else { NO_CLUE };
+        bits.write_bits(clue as u32, CLUE_BITS);
+    }
+    let payload = bits.finish();
+
+    let compressed = compress(&payload);
+    let mut bytes = vec![VERSION];
+    if compressed.len() < payload.len() {
+        bytes.push(FLAG_COMPRESSED);
+        bytes.extend(compressed);
+    } else {
+        bytes.push(0);
+        bytes.extend(payload);
+    }
+    let checksum = crc16(&bytes);
+    bytes.push((checksum >> 8) as u8);
+    bytes.push(checksum as u8);
+    encode_base64(&bytes)
+}
+
+/// Reads a puzzle back from a code made by encode_share_code.
+pub fn decode_share_code(code: &str) -> Result<Grid, String> {
+    let bytes = decode_base64(code.trim())?;
+    if bytes.len() < 4 {
+        return Err("Puzzle code is too short".to_string());
+    }
+    let (body, trailer) = bytes.split_at(bytes.len() - 2);
+    if crc16(body) != ((trailer[0] as u16) << 8 | trailer[1] as u16) {
+        return Err("Puzzle code checksum does not match".to_string());
+    }
+    if body[0] != VERSION {
+        return Err(format!("Unsupported puzzle code version {}", body[0]));
+    }
+    let payload = match body[1] {
+        0 => body[2..].to_vec(),
+        FLAG_COMPRESSED => decompress(&body[2..])?,
+        x => { return Err(format!("Unknown puzzle code flags {}", x)); }
+    };
+
+    let mut pos = 0;
+    let height = read_varint(&payload, &mut pos)?;
+    let width = read_varint(&payload, &mut pos)?;
+    let size = match height.checked_mul(width) {
+        Some(x) if height > 0 && width > 0 && (x as usize) <= 8 * (payload.len() - pos) => x as usize,
+        _ => { return Err(format!("Invalid puzzle code dimensions {}x{}", height, width)); }
+    };
+
+    let mut bits = BitReader::new(&payload[pos..]);
+    let walls = (0..size).map(|_| bits.read_bits(1).map(|x| x != 0))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("Puzzle code ends in the middle of the walls")?;
+    let mut contents = Vec::with_capacity(size);
+    for is_wall in walls {
+        if !is_wall {
+            contents.push(0);
+            continue;
+        }
+        match bits.read_bits(CLUE_BITS).ok_or("Puzzle code ends in the middle of the clues")? as u8 {
+            NO_CLUE => { contents.push(IS_SOLID); },
+            x @ 0..=4 => { contents.push(x | IS_SOLID | IS_CONSTRAINED); },
+            x => { return Err(format!("Invalid clue {} in puzzle code", x)); }
+        }
+    }
+    if !bits.at_end() {
+        return Err("Unexpected data at the end of the puzzle code".to_string());
+    }
+    Ok(Grid { contents, height: height as i32, width: width as i32 })
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: usize,
+}
+
+impl BitWriter {
+    fn new(bytes: Vec<u8>) -> BitWriter {
+        BitWriter { bytes, used: 8 }
+    }
+
+    fn write(&mut self, bit: bool) {
+        if self.used == 8 {
+            self.bytes.push(0);
+            self.used = 0;
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> self.used;
+        }
+        self.used += 1;
+    }
+
+    fn write_bits(&mut self, value: u32, count: usize) {
+        for idx in (0..count).rev() {
+            self.write(value & (1 << idx) != 0);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader { bytes, pos: 0 }
+    }
+
+    fn read_bits(&mut self, count: usize) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.bytes.get(self.pos / 8)?;
+            value = value << 1 | ((byte >> (7 - self.pos % 8)) & 1) as u32;
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    /// Whether only the zero bits padding out the last byte are left.
+    fn at_end(&mut self) -> bool {
+        let padding = (8 - self.pos % 8) % 8;
+        self.read_bits(padding) == Some(0) && self.pos / 8 == self.bytes.len()
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads a LEB128 number that fits in an i32.
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let mut value: u64 = 0;
+    for shift in 0..5 {
+        let byte = *bytes.get(*pos).ok_or("Puzzle code ends in the middle of a number")?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << (7 * shift);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    if bytes[*pos - 1] & 0x80 != 0 || value > i32::MAX as u64 {
+        return Err("Number in puzzle code is too large".to_string());
+    }
+    Ok(value as u32)
+}
+
+/**
+ * PackBits run-length compression. A header byte n up to 127 is followed by
+ * n + 1 bytes to copy, and a header byte of 257 - n for n from 2 to 128 is
+ * followed by one byte to repeat n times.
+ */
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let run = bytes[idx..].iter().take(128).take_while(|&&x| x == bytes[idx]).count();
+        if run >= 2 {
+            result.push((257 - run) as u8);
+            result.push(bytes[idx]);
+            idx += run;
+            continue;
+        }
+        let start = idx;
+        while idx < bytes.len() && idx - start < 128
+                && !(idx + 1 < bytes.len() && bytes[idx] == bytes[idx + 1]) {
+            idx += 1;
+        }
+        if idx == start {
+            idx += 1;
+        }
+        result.push((idx - start - 1) as u8);
+        result.extend_from_slice(&bytes[start..idx]);
+    }
+    result
+}
+
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let header = bytes[idx] as usize;
+        idx += 1;
+        if header < 128 {
+            let literal = bytes.get(idx..(idx + header + 1))
+                .ok_or("Puzzle code ends in the middle of a literal")?;
+            result.extend_from_slice(literal);
+            idx += header + 1;
+        } else if header > 128 {
+            let byte = *bytes.get(idx).ok_or("Puzzle code ends in the middle of a run")?;
+            result.extend(std::iter::repeat_n(byte, 257 - header));
+            idx += 1;
+        } else {
+            return Err("Invalid run length in puzzle code".to_string());
+        }
+    }
+    Ok(result)
+}
+
+/// CRC-16/CCITT-FALSE.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate()
+            .fold(0u32, |acc, (idx, &x)| acc | (x as u32) << (16 - 8 * idx));
+        for idx in 0..(chunk.len() + 1) {
+            result.push(BASE64_ALPHABET[(value >> (18 - 6 * idx) & 0x3f) as usize] as char);
+        }
+    }
+    result
+}
+
+fn decode_base64(code: &str) -> Result<Vec<u8>, String> {
+    let values = code.chars()
+        .map(|c| BASE64_ALPHABET.iter().position(|&x| x as char == c)
+             .ok_or_else(|| format!("Unexpected character '{}' in puzzle code", c)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() % 4 == 1 {
+        return Err("Puzzle code has an invalid length".to_string());
+    }
+    let mut result = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        let value = chunk.iter().enumerate()
+            .fold(0u32, |acc, (idx, &x)| acc | (x as u32) << (18 - 6 * idx));
+        for idx in 0..(chunk.len() - 1) {
+            result.push((value >> (16 - 8 * idx)) as u8);
+        }
+    }
+    Ok(result)
+}
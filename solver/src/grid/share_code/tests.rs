@@ -0,0 +1,108 @@
+use super::*;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+static TEST_GRID_STR: &str =
+"__1__X__1_
+ 0_____1___
+ _____X___0
+ _1________
+ 2_X_______
+ _______1_1
+ ________2_
+ 1___X_____
+ ___X_____X
+ _1__0__1__";
+
+fn with_checksum(bytes: &[u8]) -> String {
+    let checksum = crc16(bytes);
+    let mut result = bytes.to_vec();
+    result.extend_from_slice(&[(checksum >> 8) as u8, checksum as u8]);
+    encode_base64(&result)
+}
+
+#[test]
+fn test_round_trip() {
+    let grid = get_grid_from_string(TEST_GRID_STR, 10, 10).unwrap();
+    let code = encode_share_code(&grid);
+    assert!(code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    let decoded = decode_share_code(&code).unwrap();
+    assert_eq!(10, decoded.height);
+    assert_eq!(10, decoded.width);
+    assert_eq!(print_grid_to_string(&grid, false), print_grid_to_string(&decoded, false));
+}
+
+#[test]
+fn test_encode_share_code() {
+    let grid = get_grid_from_string(
+        "___
+         _3_
+         _X_", 3, 3).unwrap();
+    // Version 1, no flags, 3x3, walls 000010010 and clues 011 111.
+    assert_eq!(with_checksum(&[1, 0, 3, 3, 0x09, 0x3e]), encode_share_code(&grid));
+}
+
+#[test]
+fn test_compressed_round_trip() {
+    let grid = get_grid_from_string(&format!("{}1{}", "_".repeat(300), "_".repeat(99)), 20, 20)
+        .unwrap();
+    let code = encode_share_code(&grid);
+    assert!(code.len() < 20);
+    assert_eq!(print_grid_to_string(&grid, false),
+               print_grid_to_string(&decode_share_code(&code).unwrap(), false));
+}
+
+#[test]
+fn test_ignores_marks() {
+    let grid = get_grid_from_string("*#^X", 1, 4).unwrap();
+    let decoded = decode_share_code(&encode_share_code(&grid)).unwrap();
+    assert_eq!("___X", print_grid_to_string(&decoded, false));
+}
+
+#[test]
+fn test_checksum() {
+    let grid = get_grid_from_string(TEST_GRID_STR, 10, 10).unwrap();
+    let mut corrupted = encode_share_code(&grid).into_bytes();
+    corrupted[5] = if corrupted[5] == b'A' { b'B' } else { b'A' };
+    let result = decode_share_code(&String::from_utf8(corrupted).unwrap());
+    assert_eq!(Err("Puzzle code checksum does not match".to_string()), result.map(|_| ()));
+}
+
+#[test]
+fn test_decode_errors() {
+    assert!(decode_share_code(&with_checksum(&[1, 0, 3, 3, 0x09, 0x3e])).is_ok());
+    assert!(decode_share_code("").is_err());
+    assert!(decode_share_code("AAAA*AAA").is_err());
+    // Unknown version and flags.
+    assert!(decode_share_code(&with_checksum(&[2, 0, 3, 3, 0x09, 0x3e])).is_err());
+    assert!(decode_share_code(&with_checksum(&[1, 4, 3, 3, 0x09, 0x3e])).is_err());
+    // Too little data, or too much.
+    assert!(decode_share_code(&with_checksum(&[1, 0, 3, 3, 0x09])).is_err());
+    assert!(decode_share_code(&with_checksum(&[1, 0, 3, 3, 0x09, 0x3e, 0])).is_err());
+    assert!(decode_share_code(&with_checksum(&[1, 0, 3, 3, 0x09, 0x3f])).is_err());
+    // Bad dimensions, and a clue of 5.
+    assert!(decode_share_code(&with_checksum(&[1, 0, 0, 3, 0x09, 0x3e])).is_err());
+    assert!(decode_share_code(&with_checksum(&[1, 0, 0xff, 0xff, 0xff, 0xff, 0x7f, 1])).is_err());
+    assert!(decode_share_code(&with_checksum(&[1, 0, 3, 3, 0x09, 0x3a])).is_err());
+}
+
+#[test]
+fn test_compress() {
+    let bytes = [1, 2, 3, 3, 3, 3, 4];
+    let compressed = compress(&bytes);
+    assert_eq!(vec![1, 1, 2, 253, 3, 0, 4], compressed);
+    assert_eq!(bytes.to_vec(), decompress(&compressed).unwrap());
+    let long = vec![7; 300];
+    assert_eq!(long, decompress(&compress(&long)).unwrap());
+    assert!(decompress(&[128]).is_err());
+    assert!(decompress(&[3, 1]).is_err());
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!("", encode_base64(&[]));
+    assert_eq!("_-8", encode_base64(&[0xff, 0xef]));
+    for len in 0..6 {
+        let bytes: Vec<u8> = (0..len).map(|x| x * 53 + 7).collect();
+        assert_eq!(bytes, decode_base64(&encode_base64(&bytes)).unwrap());
+    }
+}
@@ -0,0 +1,167 @@
+use super::{IS_SOLID, IS_LIT, IS_LIGHT, CANT_LIGHT, IS_CONSTRAINED};
+use super::GridData;
+use super::coords::Coord;
+
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+const LIT_COLOR: &str = "#fff2a8";
+const LIGHT_COLOR: &str = "#ffd21f";
+const BEAM_COLOR: &str = "#f0a800";
+const HIGHLIGHT_COLOR: &str = "#e0301e";
+
+/// What to draw, and how big.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    /// Draw the lights, lit squares and marks in the grid, not just the puzzle.
+    pub show_solution: bool,
+    /// Draw the beam each light casts. Only used along with show_solution.
+    pub show_beams: bool,
+    /// Squares to outline, such as errors or hints.
+    pub highlights: Vec<Coord>,
+    /// The side of a square, in pixels.
+    pub cell_size: u32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            show_solution: false,
+            show_beams: false,
+            highlights: Vec::new(),
+            cell_size: 40,
+        }
+    }
+}
+
+/// The width and height of the drawing of the given grid, in pixels.
+pub fn svg_size(grid: &GridData, options: &SvgOptions) -> (u32, u32) {
+    let border = options.cell_size / 2;
+    (grid.grid.width as u32 * options.cell_size + 2 * border,
+     grid.grid.height as u32 * options.cell_size + 2 * border)
+}
+
+/// Renders the grid as a standalone SVG document.
+pub fn render_svg(grid: &GridData, options: &SvgOptions) -> String {
+    let (width, height) = svg_size(grid, options);
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\">\n{2}</svg>\n",
+            width, height, render_fragment(grid, options))
+}
+
+/**
+ * Renders the grid as a group of SVG elements, with the top left corner of
+ * the drawing at the origin, so that it can be placed within a larger
+ * document. The drawing takes up svg_size(grid, options).
+ */
+pub fn render_fragment(grid: &GridData, options: &SvgOptions) -> String {
+    let size = options.cell_size;
+    let border = size / 2;
+    let width = grid.grid.width as u32;
+    let cell_origin = |loc: usize| {
+        (border + (loc as u32 % width) * size, border + (loc as u32 / width) * size)
+    };
+    let cell_center = |loc: usize| {
+        let (x, y) = cell_origin(loc);
+        (x + size / 2, y + size / 2)
+    };
+
+    let mut out = String::new();
+    out.push_str("<g>\n");
+    for (loc, &square) in grid.grid.contents.iter().enumerate() {
+        let (x, y) = cell_origin(loc);
+        let fill = if square & IS_SOLID != 0 {
+            "black"
+        } else if options.show_solution && square & (IS_LIT | IS_LIGHT) != 0 {
+            LIT_COLOR
+        } else {
+            "white"
+        };
+        writeln!(out, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" \
+                       stroke=\"#808080\" stroke-width=\"1\"/>",
+                 x, y, size, size, fill).unwrap();
+        if square & IS_CONSTRAINED != 0 {
+            let (cx, cy) = cell_center(loc);
+            writeln!(out, "<text x=\"{}\" y=\"{}\" fill=\"white\" font-family=\"sans-serif\" \
+                           font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">\
+                           {}</text>",
+                     cx, cy, size * 3 / 5, square & CONSTRAINT_NUM_MASK).unwrap();
+        }
+    }
+
+    let (total_width, total_height) = svg_size(grid, options);
+    writeln!(out, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" \
+                   stroke=\"black\" stroke-width=\"2\"/>",
+             border, border, total_width - 2 * border, total_height - 2 * border).unwrap();
+
+    if options.show_solution {
+        if options.show_beams {
+            for loc in lights(grid) {
+                let (cx, cy) = cell_center(loc);
+                for end in beam_ends(grid, loc) {
+                    // Stop at the far edge of the last square rather than its middle.
+                    let (ex, ey) = cell_center(end);
+                    let reach = |from: u32, to: u32| {
+                        if to > from { to + size / 2 } else if to < from { to - size / 2 } else { to }
+                    };
+                    writeln!(out, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+                                   stroke-width=\"{}\" stroke-linecap=\"round\"/>",
+                             cx, cy, reach(cx, ex), reach(cy, ey), BEAM_COLOR, size / 10 + 1).unwrap();
+                }
+            }
+        }
+        for (loc, &square) in grid.grid.contents.iter().enumerate() {
+            let (cx, cy) = cell_center(loc);
+            if square & IS_SOLID != 0 {
+                continue;
+            }
+            if square & IS_LIGHT != 0 {
+                writeln!(out, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\" \
+                               stroke-width=\"2\"/>",
+                         cx, cy, size * 3 / 10, LIGHT_COLOR).unwrap();
+            } else if square & CANT_LIGHT != 0 {
+                writeln!(out, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>",
+                         cx, cy, size / 15 + 1).unwrap();
+            }
+        }
+    }
+
+    for loc in options.highlights.iter().filter_map(|&x| grid.grid.index_of(x)) {
+        let (x, y) = cell_origin(loc);
+        writeln!(out, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" \
+                       fill-opacity=\"0.25\" stroke=\"{}\" stroke-width=\"3\"/>",
+                 x + 2, y + 2, size - 4, size - 4, HIGHLIGHT_COLOR, HIGHLIGHT_COLOR).unwrap();
+    }
+    out.push_str("</g>\n");
+    out
+}
+
+fn lights(grid: &GridData) -> Vec<usize> {
+    (0..grid.grid.contents.len())
+        .filter(|&x| grid.grid.contents[x] & (IS_LIGHT | IS_SOLID) == IS_LIGHT)
+        .collect()
+}
+
+/**
+ * The last squares the light at loc shines on in each direction, found from
+ * its sight line. A light with nothing to light in some direction has no
+ * beam that way.
+ */
+fn beam_ends(grid: &GridData, loc: usize) -> Vec<usize> {
+    let width = grid.grid.width as usize;
+    let sight_line = match grid.sight_lines.get(&loc) {
+        Some(x) => x,
+        None => { return Vec::new(); }
+    };
+    let same_row = |x: usize| x / width == loc / width;
+    let ends = [
+        sight_line.iter().filter(|&&x| same_row(x) && x < loc).min(),
+        sight_line.iter().filter(|&&x| same_row(x) && x > loc).max(),
+        sight_line.iter().filter(|&&x| !same_row(x) && x < loc).min(),
+        sight_line.iter().filter(|&&x| !same_row(x) && x > loc).max(),
+    ];
+    ends.iter().filter_map(|x| x.cloned()).collect()
+}
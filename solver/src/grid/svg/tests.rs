@@ -0,0 +1,83 @@
+use super::*;
+use super::super::solve_puzzle;
+use super::super::dpll::solve;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+fn solved_example() -> GridData {
+    let mut grid = precompute_data(get_grid_from_string("____3__X_", 3, 3).unwrap());
+    solve_puzzle(&mut grid, solve).unwrap();
+    grid
+}
+
+#[test]
+fn test_svg_size() {
+    let grid = precompute_data(get_grid_from_string("______", 2, 3).unwrap());
+    assert_eq!((160, 120), svg_size(&grid, &SvgOptions::default()));
+    let options = SvgOptions { cell_size: 10, ..SvgOptions::default() };
+    assert_eq!((40, 30), svg_size(&grid, &options));
+}
+
+#[test]
+fn test_render_svg_document() {
+    let grid = precompute_data(get_grid_from_string("_X", 1, 2).unwrap());
+    let svg = render_svg(&grid, &SvgOptions::default());
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"80\" \
+                             viewBox=\"0 0 120 80\">\n<g>\n"));
+    assert!(svg.ends_with("</g>\n</svg>\n"));
+}
+
+#[test]
+fn test_render_puzzle_view() {
+    let grid = solved_example();
+    let svg = render_fragment(&grid, &SvgOptions::default());
+    assert!(svg.contains("<rect x=\"60\" y=\"100\" width=\"40\" height=\"40\" fill=\"black\""));
+    assert!(svg.contains("<rect x=\"60\" y=\"60\" width=\"40\" height=\"40\" fill=\"black\""));
+    assert!(svg.contains(">3</text>"));
+    assert_eq!(1, svg.matches("<text").count());
+    assert!(!svg.contains("<circle"));
+    assert!(!svg.contains(LIT_COLOR));
+}
+
+#[test]
+fn test_render_solution_view() {
+    let grid = solved_example();
+    let options = SvgOptions { show_solution: true, ..SvgOptions::default() };
+    let svg = render_fragment(&grid, &options);
+    assert_eq!(3, svg.matches(LIGHT_COLOR).count());
+    assert!(svg.contains("<circle cx=\"80\" cy=\"40\""));
+    assert!(svg.contains("<rect x=\"20\" y=\"20\" width=\"40\" height=\"40\" fill=\"#fff2a8\""));
+    assert!(!svg.contains("<line"));
+}
+
+#[test]
+fn test_render_beams() {
+    let grid = solved_example();
+    let options = SvgOptions { show_solution: true, show_beams: true, ..SvgOptions::default() };
+    let svg = render_fragment(&grid, &options);
+    assert_eq!(6, svg.matches("<line").count());
+    assert!(svg.contains("<line x1=\"80\" y1=\"40\" x2=\"20\" y2=\"40\""));
+    assert!(svg.contains("<line x1=\"80\" y1=\"40\" x2=\"140\" y2=\"40\""));
+    assert!(svg.contains("<line x1=\"40\" y1=\"80\" x2=\"40\" y2=\"140\""));
+    // The beams are drawn underneath the bulbs.
+    assert!(svg.rfind("<line").unwrap() < svg.find("<circle").unwrap());
+}
+
+#[test]
+fn test_beam_ends() {
+    let grid = precompute_data(get_grid_from_string("___X____", 2, 4).unwrap());
+    let mut ends = beam_ends(&grid, 1);
+    ends.sort();
+    assert_eq!(vec![0, 2, 5], ends);
+    assert_eq!(vec![4], beam_ends(&grid, 0).into_iter().filter(|&x| x > 3).collect::<Vec<_>>());
+    assert!(beam_ends(&grid, 3).is_empty());
+}
+
+#[test]
+fn test_render_highlights() {
+    let grid = solved_example();
+    let highlights = vec![Coord::new(0, 0), Coord::new(2, 2), Coord::new(0, 100)];
+    let options = SvgOptions { highlights, ..SvgOptions::default() };
+    let svg = render_fragment(&grid, &options);
+    assert_eq!(2, svg.matches("fill-opacity").count());
+    assert!(svg.contains("<rect x=\"22\" y=\"22\" width=\"36\" height=\"36\" fill=\"#e0301e\""));
+}
@@ -0,0 +1,93 @@
+use super::{IS_SOLID, IS_CONSTRAINED};
+use super::Grid;
+
+#[cfg(test)]
+mod tests;
+
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+/// The longest run of empty squares a single letter can stand for.
+const MAX_RUN: u8 = 26;
+
+/**
+ * Reads a puzzle from a game ID of the Light Up game in Simon Tatham's
+ * Portable Puzzle Collection, such as 7x7:b1c0B... The parameters before
+ * the colon start with the width and height, and any generation options
+ * after them are ignored. In the description, a letter stands for a run of
+ * 1 to 26 empty squares, B for a wall, and a digit for a clue.
+ */
+pub fn decode_game_id(id: &str) -> Result<Grid, String> {
+    let id = id.trim();
+    let (params, desc) = match id.find(':') {
+        Some(idx) => (&id[..idx], &id[(idx + 1)..]),
+        None => {
+            return Err(format!("Not a game ID, expected parameters and a description: {}", id));
+        }
+    };
+    let (width, height) = parse_params(params)?;
+    let size = width.checked_mul(height).ok_or("Puzzle is too large")? as usize;
+
+    let mut contents = Vec::new();
+    for c in desc.chars() {
+        match c {
+            'a'..='z' => {
+                let run = c as usize - 'a' as usize + 1;
+                contents.extend(std::iter::repeat_n(0, run));
+            },
+            'B' => { contents.push(IS_SOLID); },
+            '0'..='4' => { contents.push((c as u8 - b'0') | IS_SOLID | IS_CONSTRAINED); },
+            _ => { return Err(format!("Unexpected character '{}' in game ID", c)); }
+        }
+        if contents.len() > size {
+            return Err(format!("Game ID describes more than {} squares", size));
+        }
+    }
+    if contents.len() < size {
+        return Err(format!("Game ID describes {} squares, expected {}", contents.len(), size));
+    }
+    Ok(Grid { contents, height, width })
+}
+
+/// Writes the walls and clues of a puzzle as a Tatham game ID.
+pub fn encode_game_id(grid: &Grid) -> String {
+    let mut desc = String::new();
+    let mut run = 0;
+    for &square in grid.contents.iter() {
+        if square & IS_SOLID == 0 {
+            if run == MAX_RUN {
+                desc.push(run_char(run));
+                run = 0;
+            }
+            run += 1;
+            continue;
+        }
+        if run > 0 {
+            desc.push(run_char(run));
+            run = 0;
+        }
+        if square & IS_CONSTRAINED != 0 {
+            desc.push((b'0' + (square & CONSTRAINT_NUM_MASK)) as char);
+        } else {
+            desc.push('B');
+        }
+    }
+    if run > 0 {
+        desc.push(run_char(run));
+    }
+    format!("{}x{}:{}", grid.width, grid.height, desc)
+}
+
+/// Reads the width and height from the start of the game parameters.
+fn parse_params(params: &str) -> Result<(i32, i32), String> {
+    let dims = params.find(|c: char| !c.is_ascii_digit() && c != 'x').map_or(params, |idx| &params[..idx]);
+    let mut parts = dims.splitn(2, 'x');
+    let width = parts.next().and_then(|x| x.parse::<i32>().ok());
+    let height = parts.next().and_then(|x| x.parse::<i32>().ok());
+    match (width, height) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!("Invalid game parameters: {}", params)),
+    }
+}
+
+fn run_char(run: u8) -> char {
+    (b'a' + run - 1) as char
+}
@@ -0,0 +1,66 @@
+use super::*;
+use super::super::utils::{get_grid_from_string, print_grid_to_string};
+
+#[test]
+fn test_decode_game_id() {
+    let grid = decode_game_id("3x3:d3bBa").unwrap();
+    assert_eq!(3, grid.height);
+    assert_eq!(3, grid.width);
+    assert_eq!("____3__X_", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_decode_rectangular() {
+    // Width comes before height in the parameters.
+    let grid = decode_game_id("4x2b20s2d1:1Bb0c\n").unwrap();
+    assert_eq!(2, grid.height);
+    assert_eq!(4, grid.width);
+    assert_eq!("1X__0___", print_grid_to_string(&grid, false));
+}
+
+#[test]
+fn test_encode_game_id() {
+    let grid = get_grid_from_string(
+        "___
+         _3_
+         _X_", 3, 3).unwrap();
+    assert_eq!("3x3:d3bBa", encode_game_id(&grid));
+}
+
+#[test]
+fn test_encode_long_runs() {
+    let grid = get_grid_from_string(&format!("{}4{}", "_".repeat(30), "*#^_"), 1, 35).unwrap();
+    let id = encode_game_id(&grid);
+    assert_eq!("35x1:zd4d", id);
+    assert_eq!("_".repeat(30) + "4____",
+               print_grid_to_string(&decode_game_id(&id).unwrap(), false));
+}
+
+#[test]
+fn test_round_trip() {
+    let test_grid_str =
+        "__1__X__1_
+         0_____1___
+         _____X___0
+         _1________
+         2_X_______
+         _______1_1
+         ________2_
+         1___X_____
+         ___X_____X
+         _1__0__1__";
+    let grid = get_grid_from_string(test_grid_str, 10, 10).unwrap();
+    let decoded = decode_game_id(&encode_game_id(&grid)).unwrap();
+    assert_eq!(print_grid_to_string(&grid, false), print_grid_to_string(&decoded, false));
+}
+
+#[test]
+fn test_decode_errors() {
+    assert!(decode_game_id("3x3").is_err());
+    assert!(decode_game_id("3x3#12345").is_err());
+    assert!(decode_game_id("3:i").is_err());
+    assert!(decode_game_id("0x3:").is_err());
+    assert!(decode_game_id("3x3:d3bB").is_err());
+    assert!(decode_game_id("3x3:d3bBaa").is_err());
+    assert!(decode_game_id("3x3:d5bBa").is_err());
+}
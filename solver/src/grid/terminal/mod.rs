@@ -0,0 +1,92 @@
+use super::{IS_SOLID, IS_LIT, IS_LIGHT, CANT_LIGHT, IS_CONSTRAINED};
+use super::GridData;
+use super::coords::Coord;
+
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+/// Each square takes up this many columns of the terminal.
+const CELL_WIDTH: usize = 3;
+const RESET: &str = "\x1b[0m";
+const LIGHT_STYLE: &str = "1;33";
+const CLUE_STYLE: &str = "1;7";
+const WALL_STYLE: &str = "90";
+const LIT_STYLE: &str = "33";
+const CANT_LIGHT_STYLE: &str = "2";
+const HIGHLIGHT_STYLE: &str = "41";
+
+/// How the grid is drawn in the terminal.
+#[derive(Clone, Debug, Default)]
+pub struct TerminalOptions {
+    /// Use ANSI escape codes to colour the squares.
+    pub color: bool,
+    /// Squares to highlight, such as errors or hints.
+    pub highlights: Vec<Coord>,
+}
+
+/**
+ * Draws the grid for a terminal, inside a box-drawing border with the column
+ * numbers above it and the row numbers to its left. Squares are drawn with the
+ * same precedence as print_grid_to_string: a light is a bulb, a clue its
+ * number, a wall a block, a lit square a dot and a square that can't hold a
+ * light a cross. Without colour, highlighted squares are put in brackets.
+ */
+pub fn render_terminal(grid: &GridData, options: &TerminalOptions) -> String {
+    let width = grid.grid.width as usize;
+    let height = grid.grid.height as usize;
+    let label_width = height.saturating_sub(1).to_string().len();
+    let inner_width = width * CELL_WIDTH;
+
+    let mut header = " ".repeat(label_width + 2);
+    for column in 0..width {
+        write!(header, "{:^width$}", column, width = CELL_WIDTH).unwrap();
+    }
+    let mut out = String::new();
+    writeln!(out, "{}", header.trim_end()).unwrap();
+    writeln!(out, "{} \u{250c}{}\u{2510}", " ".repeat(label_width), "\u{2500}".repeat(inner_width)).unwrap();
+    for (row, squares) in grid.grid.contents.chunks(width).enumerate() {
+        write!(out, "{:>width$} \u{2502}", row, width = label_width).unwrap();
+        for (column, &square) in squares.iter().enumerate() {
+            let highlighted = options.highlights.contains(&Coord::new(row, column));
+            out.push_str(&render_square(square, highlighted, options.color));
+        }
+        out.push_str("\u{2502}\n");
+    }
+    writeln!(out, "{} \u{2514}{}\u{2518}", " ".repeat(label_width), "\u{2500}".repeat(inner_width)).unwrap();
+    out
+}
+
+fn render_square(square: u8, highlighted: bool, color: bool) -> String {
+    let (symbol, style) = if square & IS_LIGHT != 0 {
+        ('\u{25cf}', LIGHT_STYLE)
+    } else if square & IS_CONSTRAINED != 0 {
+        ((b'0' + (square & CONSTRAINT_NUM_MASK)) as char, CLUE_STYLE)
+    } else if square & IS_SOLID != 0 {
+        ('\u{2588}', WALL_STYLE)
+    } else if square & IS_LIT != 0 {
+        ('\u{00b7}', LIT_STYLE)
+    } else if square & CANT_LIGHT != 0 {
+        ('\u{00d7}', CANT_LIGHT_STYLE)
+    } else {
+        (' ', "")
+    };
+    // Walls without clues fill their whole square.
+    let fill = if style == WALL_STYLE { symbol } else { ' ' };
+
+    if !color {
+        let (left, right) = if highlighted { ('[', ']') } else { (fill, fill) };
+        return format!("{}{}{}", left, symbol, right);
+    }
+    let style = match (highlighted, style.is_empty()) {
+        (false, _) => style.to_string(),
+        (true, true) => HIGHLIGHT_STYLE.to_string(),
+        (true, false) => format!("{};{}", style, HIGHLIGHT_STYLE),
+    };
+    if style.is_empty() {
+        return format!("{}{}{}", fill, symbol, fill);
+    }
+    format!("\x1b[{}m{}{}{}{}", style, fill, symbol, fill, RESET)
+}
@@ -0,0 +1,64 @@
+use super::*;
+use super::super::solve_puzzle;
+use super::super::dpll::solve;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+fn solved_example() -> GridData {
+    let mut grid = precompute_data(get_grid_from_string("____3__X_", 3, 3).unwrap());
+    solve_puzzle(&mut grid, solve).unwrap();
+    grid
+}
+
+#[test]
+fn test_render_terminal_plain() {
+    let expected = concat!(
+        "    0  1  2\n",
+        "  \u{250c}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2510}\n",
+        "0 \u{2502} \u{b7}  \u{25cf}  \u{b7} \u{2502}\n",
+        "1 \u{2502} \u{25cf}  3  \u{25cf} \u{2502}\n",
+        "2 \u{2502} \u{b7} \u{2588}\u{2588}\u{2588} \u{b7} \u{2502}\n",
+        "  \u{2514}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2518}\n");
+    assert_eq!(expected, render_terminal(&solved_example(), &TerminalOptions::default()));
+}
+
+#[test]
+fn test_render_terminal_marks() {
+    let grid = precompute_data(get_grid_from_string("^_", 1, 2).unwrap());
+    let rendered = render_terminal(&grid, &TerminalOptions::default());
+    assert!(rendered.contains("0 \u{2502} \u{d7}    \u{2502}\n"));
+}
+
+#[test]
+fn test_render_terminal_wide_labels() {
+    let grid = precompute_data(get_grid_from_string(&"_".repeat(121), 11, 11).unwrap());
+    let rendered = render_terminal(&grid, &TerminalOptions::default());
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(14, lines.len());
+    assert!(lines[0].starts_with("     0  1"));
+    assert!(lines[0].ends_with(" 9 10"));
+    assert!(lines[2].starts_with(" 0 \u{2502}"));
+    assert!(lines[12].starts_with("10 \u{2502}"));
+}
+
+#[test]
+fn test_render_terminal_highlights() {
+    let options = TerminalOptions { color: false, highlights: vec![Coord::new(0, 1), Coord::new(1, 1)] };
+    let rendered = render_terminal(&solved_example(), &options);
+    assert!(rendered.contains("0 \u{2502} \u{b7} [\u{25cf}] \u{b7} \u{2502}"));
+    assert!(rendered.contains("1 \u{2502} \u{25cf} [3] \u{25cf} \u{2502}"));
+}
+
+#[test]
+fn test_render_terminal_color() {
+    let options = TerminalOptions { color: true, highlights: vec![Coord::new(0, 0)] };
+    let rendered = render_terminal(&solved_example(), &options);
+    assert!(rendered.contains("\x1b[1;33m \u{25cf} \x1b[0m"));
+    assert!(rendered.contains("\x1b[1;7m 3 \x1b[0m"));
+    assert!(rendered.contains("\x1b[90m\u{2588}\u{2588}\u{2588}\x1b[0m"));
+    assert!(rendered.contains("\x1b[33;41m \u{b7} \x1b[0m"));
+    assert!(rendered.contains("\x1b[33m \u{b7} \x1b[0m"));
+
+    let grid = precompute_data(get_grid_from_string("_", 1, 1).unwrap());
+    let rendered = render_terminal(&grid, &options);
+    assert!(rendered.contains("\u{2502}\x1b[41m   \x1b[0m\u{2502}"));
+}
@@ -14,10 +14,10 @@ mod tests;
 
 #[allow(unused_variables)]
 static EDGE_SPECIFIERS: [fn(usize, usize, usize) -> bool; 4] = [
-    { fn t(loc: usize, height: usize, width: usize) -> bool { loc < width }; t},
-    { fn t(loc: usize, height: usize, width: usize) -> bool { loc % width == 0}; t}, 
-    { fn t(loc: usize, height: usize, width: usize) -> bool { loc + width >= height * width }; t},
-    { fn t(loc: usize, height: usize, width: usize) -> bool { loc % width == width - 1 }; t}
+    { fn t(loc: usize, height: usize, width: usize) -> bool { loc < width } t},
+    { fn t(loc: usize, height: usize, width: usize) -> bool { loc.is_multiple_of(width) } t}, 
+    { fn t(loc: usize, height: usize, width: usize) -> bool { loc + width >= height * width } t},
+    { fn t(loc: usize, height: usize, width: usize) -> bool { loc % width == width - 1 } t}
 ];
 
 static EDGE_INVALIDATED_POSITIONS: [[usize; 3]; 4] = [
@@ -29,14 +29,14 @@ static EDGE_INVALIDATED_POSITIONS: [[usize; 3]; 4] = [
 
 #[allow(unused_variables)]
 static RELATIVE_POSITION_SPECIFIERS: [fn(usize, usize) -> usize; 8] = [
-    { fn t(loc: usize, width: usize) -> usize { loc - width }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc + 1 }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc + width }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc - 1 }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc - width - 1 }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc - width + 1 }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc + width + 1 }; t},
-    { fn t(loc: usize, width: usize) -> usize { loc + width - 1 }; t},
+    { fn t(loc: usize, width: usize) -> usize { loc - width } t},
+    { fn t(loc: usize, width: usize) -> usize { loc + 1 } t},
+    { fn t(loc: usize, width: usize) -> usize { loc + width } t},
+    { fn t(loc: usize, width: usize) -> usize { loc - 1 } t},
+    { fn t(loc: usize, width: usize) -> usize { loc - width - 1 } t},
+    { fn t(loc: usize, width: usize) -> usize { loc - width + 1 } t},
+    { fn t(loc: usize, width: usize) -> usize { loc + width + 1 } t},
+    { fn t(loc: usize, width: usize) -> usize { loc + width - 1 } t},
 ];
 
 pub fn get_neighbors(grid: &GridData, loc: usize) -> ([bool; 8], [usize; 8]) {
@@ -88,7 +88,7 @@ pub fn precompute_data(grid: Grid) -> GridData {
             sight_lines.insert(i as usize, get_sight_line(&grid, i));
         }
     }
-    GridData {grid: grid, sight_lines: sight_lines}
+    GridData {grid, sight_lines}
 }
 
 pub fn get_sight_line(grid: &Grid, idx: i32) -> Vec<usize> {
@@ -156,7 +156,7 @@ pub fn get_grid_from_string(input: &str, height: i32, width: i32) -> Result<Grid
             _ => 0
         });
     }
-    Ok(Grid {contents: data, height: height, width: width})
+    Ok(Grid {contents: data, height, width})
 }
 
 pub fn print_griddata_to_string(grid: &GridData, pretty_print: bool) -> String {
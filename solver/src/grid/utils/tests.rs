@@ -2,7 +2,7 @@ use super::*;
 use std::collections::HashSet;
 use std::collections::HashMap;
 
-static TEST_GRID_STR: &'static str = "
+static TEST_GRID_STR: &str = "
 ____X
 X1__X
 XX__X
@@ -57,7 +57,7 @@ fn test_print_write_grid() {
 fn test_sight_line() {
     let test_grid_1 = get_grid_from_string(TEST_GRID_STR, 5, 5).unwrap();
 
-    let test_locations = vec!(0, 1, 8, 20);
+    let test_locations = [0, 1, 8, 20];
     let expected_sight_lines: Vec<HashSet<usize>> = vec!(
         vec!(1, 2, 3).into_iter().collect(),
         vec!(0, 2, 3).into_iter().collect(),
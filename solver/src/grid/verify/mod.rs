@@ -0,0 +1,99 @@
+use super::{IS_SOLID, IS_CONSTRAINED, INVALID_POSITION};
+use super::{Grid, GridData, SatSolver, try_solve};
+use super::cnf_format::make_cnf_formula;
+use super::coords::Coord;
+use super::utils::{get_neighbors, precompute_data};
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod tests;
+
+const CONSTRAINT_NUM_MASK: u8 = 0x7;
+
+/// What is wrong with a proposed solution, if anything.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    /// Whether the lights solve the puzzle.
+    pub solved: bool,
+    /// Lights that shine on another light.
+    pub conflicts: Vec<Coord>,
+    /// Clues without exactly their number of lights next to them.
+    pub wrong_clues: Vec<Coord>,
+    /// Empty squares that no light shines on.
+    pub unlit: Vec<Coord>,
+}
+
+/**
+ * Checks whether the given lights solve the puzzle. Only the walls and clues
+ * of the grid are looked at, so any lights or marks already in it are
+ * ignored. Returns an error if a light is outside the grid or on a wall.
+ */
+pub fn verify_lights(grid: &GridData, lights: &[Coord]) -> Result<Verification, String> {
+    let mut light_locs = HashSet::new();
+    for &light in lights {
+        let loc = grid.grid.index_of(light)
+            .ok_or_else(|| format!("Light {} is outside the grid", light))?;
+        if grid.grid.contents[loc] & IS_SOLID != 0 {
+            return Err(format!("Light {} is on a wall", light));
+        }
+        light_locs.insert(loc);
+    }
+
+    let mut conflicts = Vec::new();
+    let mut wrong_clues = Vec::new();
+    let mut unlit = Vec::new();
+    for (loc, &square) in grid.grid.contents.iter().enumerate() {
+        if square & IS_CONSTRAINED != 0 {
+            let (_, neighbors) = get_neighbors(grid, loc);
+            let num_lights = neighbors[..4].iter()
+                .filter(|&&x| x != INVALID_POSITION && light_locs.contains(&x))
+                .count();
+            if num_lights != (square & CONSTRAINT_NUM_MASK) as usize {
+                wrong_clues.push(grid.grid.coord_of(loc));
+            }
+        }
+        if square & IS_SOLID != 0 {
+            continue;
+        }
+        let seen_by_light = grid.sight_lines[&loc].iter().any(|x| light_locs.contains(x));
+        if light_locs.contains(&loc) && seen_by_light {
+            conflicts.push(grid.grid.coord_of(loc));
+        } else if !light_locs.contains(&loc) && !seen_by_light {
+            unlit.push(grid.grid.coord_of(loc));
+        }
+    }
+
+    Ok(Verification {
+        solved: conflicts.is_empty() && wrong_clues.is_empty() && unlit.is_empty(),
+        conflicts,
+        wrong_clues,
+        unlit,
+    })
+}
+
+/**
+ * Checks whether the given lights are part of some solution of the puzzle,
+ * so that a player who placed them is not yet wrong. Like verify_lights,
+ * only the walls and clues of the grid are looked at, and a light outside
+ * the grid or on a wall is an error, and so is a failure of the solver.
+ */
+pub fn can_complete(grid: &GridData, lights: &[Coord], solver: SatSolver) -> Result<bool, String> {
+    let blank = grid.grid.contents.iter()
+        .map(|&x| if x & IS_SOLID != 0 { x } else { 0 })
+        .collect();
+    let blank = precompute_data(Grid { contents: blank, ..grid.grid.clone() });
+    let formula = make_cnf_formula(&blank);
+    let mut units = Vec::with_capacity(lights.len());
+    for &light in lights {
+        let loc = grid.grid.index_of(light)
+            .ok_or_else(|| format!("Light {} is outside the grid", light))?;
+        match formula.variable_for(loc) {
+            Some(x) => { units.push(vec![x]); },
+            None => { return Err(format!("Light {} is on a wall", light)); }
+        }
+    }
+    Ok(try_solve(solver, &formula.append_clauses(units))?.is_some())
+}
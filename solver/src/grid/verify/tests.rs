@@ -0,0 +1,72 @@
+use super::*;
+use super::super::cnf_format::CnfFormula;
+use super::super::dpll::solve;
+use super::super::utils::{get_grid_from_string, precompute_data};
+
+fn example() -> GridData {
+    precompute_data(get_grid_from_string("____3__X_", 3, 3).unwrap())
+}
+
+#[test]
+fn test_verify_solution() {
+    let lights = [Coord::new(0, 1), Coord::new(1, 0), Coord::new(1, 2)];
+    let result = verify_lights(&example(), &lights).unwrap();
+    assert_eq!(Verification {
+        solved: true,
+        conflicts: vec![],
+        wrong_clues: vec![],
+        unlit: vec![],
+    }, result);
+}
+
+#[test]
+fn test_verify_mistakes() {
+    let lights = [Coord::new(0, 0), Coord::new(0, 1), Coord::new(1, 2)];
+    let result = verify_lights(&example(), &lights).unwrap();
+    assert!(!result.solved);
+    assert_eq!(vec![Coord::new(0, 0), Coord::new(0, 1)], result.conflicts);
+    assert_eq!(vec![Coord::new(1, 1)], result.wrong_clues);
+    assert!(result.unlit.is_empty());
+
+    let result = verify_lights(&example(), &[]).unwrap();
+    assert_eq!(vec![Coord::new(1, 1)], result.wrong_clues);
+    assert_eq!(7, result.unlit.len());
+}
+
+#[test]
+fn test_verify_ignores_marks_in_grid() {
+    let grid = precompute_data(get_grid_from_string("*#", 1, 2).unwrap());
+    let result = verify_lights(&grid, &[Coord::new(0, 1)]).unwrap();
+    assert!(result.solved);
+}
+
+#[test]
+fn test_verify_invalid_lights() {
+    assert_eq!(Err("Light (1, 1) is on a wall".to_string()),
+               verify_lights(&example(), &[Coord::new(1, 1)]));
+    assert_eq!(Err("Light (0, 3) is outside the grid".to_string()),
+               verify_lights(&example(), &[Coord::new(0, 3)]));
+}
+
+#[test]
+fn test_can_complete() {
+    let grid = example();
+    assert_eq!(Ok(true), can_complete(&grid, &[], solve));
+    assert_eq!(Ok(true), can_complete(&grid, &[Coord::new(0, 1)], solve));
+    assert_eq!(Ok(true), can_complete(&grid, &[Coord::new(0, 1), Coord::new(1, 0), Coord::new(1, 2)],
+                                      solve));
+    assert_eq!(Ok(false), can_complete(&grid, &[Coord::new(0, 0)], solve));
+    assert_eq!(Err("Light (2, 1) is on a wall".to_string()),
+               can_complete(&grid, &[Coord::new(2, 1)], solve));
+
+    // Any solution will do, not just one of them.
+    let ambiguous = precompute_data(get_grid_from_string("__\n__", 2, 2).unwrap());
+    assert_eq!(Ok(true), can_complete(&ambiguous, &[Coord::new(0, 0)], solve));
+    assert_eq!(Ok(true), can_complete(&ambiguous, &[Coord::new(0, 1)], solve));
+    assert_eq!(Ok(false), can_complete(&ambiguous, &[Coord::new(0, 0), Coord::new(0, 1)], solve));
+
+    fn broken(_: &CnfFormula) -> Result<Vec<i32>, String> {
+        Err("Error executing SAT solver".to_string())
+    }
+    assert_eq!(Err("Error executing SAT solver".to_string()), can_complete(&grid, &[], broken));
+}
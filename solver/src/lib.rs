@@ -1 +1,4 @@
+extern crate serde;
+extern crate serde_json;
+
 pub mod grid;
@@ -1,21 +1,75 @@
 extern crate akari_solver;
 extern crate getopts;
+extern crate serde_json;
 
 use std::io;
-use std::io::Read;
-use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::path::Path;
+use std::fs::{self, File};
 use std::process::Command;
 use std::env;
+use std::str::FromStr;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use getopts::Options;
 
 use akari_solver::grid as solver;
 use akari_solver::grid::utils;
 use akari_solver::grid::cnf_format;
+use akari_solver::grid::rules;
+use akari_solver::grid::grade;
+use akari_solver::grid::certify;
+use akari_solver::grid::parser::{self, Dialect};
+use akari_solver::grid::puzzlink;
+use akari_solver::grid::pzprv3;
+use akari_solver::grid::tatham;
+use akari_solver::grid::share_code;
+use akari_solver::grid::svg::{self, SvgOptions};
+use akari_solver::grid::booklet::{self, BookletOptions, BookletPuzzle};
+use akari_solver::grid::terminal::{self, TerminalOptions};
+use akari_solver::grid::coords::{Coord, CoordStyle};
+use akari_solver::grid::batch::{self, BatchPuzzle, Outcome};
+use akari_solver::grid::serve::Server;
+use akari_solver::grid::http::{self, App};
+use akari_solver::grid::generate::{self, GenerateOptions, Rng, Symmetry};
+use akari_solver::grid::json::Puzzle;
+use akari_solver::grid::clues;
+use akari_solver::grid::backbone;
+use akari_solver::grid::pbm;
+use akari_solver::grid::dpll;
 
-static CNF_OUT: &'static str = "/tmp/akari-solver-cnf-out.cnf";
-static RESULT_IN: &'static str = "/tmp/akari-solver-result-in.cnf";
-static SAT_SOLVER_ENV_NAME: &'static str = "SAT_SOLVER";
+static CNF_OUT: &str = "/tmp/akari-solver-cnf-out";
+static RESULT_IN: &str = "/tmp/akari-solver-result-in";
+static SAT_SOLVER_ENV_NAME: &str = "SAT_SOLVER";
+static NO_COLOR_ENV_NAME: &str = "NO_COLOR";
+
+/// The file formats puzzles can be read and written in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Pzprv3,
+    Tatham,
+    Code,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "pzprv3" => Ok(Format::Pzprv3),
+            "tatham" => Ok(Format::Tatham),
+            "code" => Ok(Format::Code),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format {}, expected text, pzprv3, tatham, code or json", s)),
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -23,10 +77,50 @@ fn main() {
 
     options.optflag("p", "pretty-print", "Pretty-print the solution.");
     options.optflag("u", "unique-only", "Only print whether the solution is unique.");
+    options.optflag("g", "grade", "Grade the difficulty of the puzzle instead of solving it.");
+    options.optopt("l", "lookahead", "Apply the rules with lookahead of the given depth.", "DEPTH");
+    options.optopt("e", "explain", "Explain which rule applications force the square.", "LOC");
+    options.optflag("c", "certify", "Check every rule deduction against the SAT solver.");
+    options.optopt("d", "dialect", "Read the puzzle in the given notation.", "native|plain");
+    options.optopt("f", "format", "Read and write puzzles in the given format.",
+                   "text|pzprv3|tatham|code|json");
+    options.optopt("i", "url", "Read the puzzle from a puzz.link URL instead.", "URL");
+    options.optflag("o", "print-url", "Print a puzz.link URL for the puzzle instead of solving it.");
+    options.optflag("t", "print-id", "Print a Tatham game ID for the puzzle instead of solving it.");
+    options.optflag("s", "print-code", "Print a puzzle code for the puzzle instead of solving it.");
+    options.optopt("v", "svg", "Draw the puzzle as SVG instead.", "puzzle|solution|beams");
+    options.optopt("H", "highlight", "Highlight the given squares in the drawing.", "LOCS");
+    options.optopt("b", "booklet", "Lay out the puzzle files given as arguments as SVG pages.",
+                   "PREFIX");
+    options.optopt("T", "title", "The title printed at the top of booklet pages.", "TITLE");
+    options.optopt("C", "coords", "Read and write squares as indices or rows and columns.",
+                   "flat|0-based|1-based");
+    options.optopt("B", "batch", "Solve every puzzle in the file, or every .grid file in the directory.",
+                   "PATH");
+    options.optopt("r", "report", "Write the batch report in the given format.", "jsonl|csv");
+    options.optopt("j", "jobs", "Solve the batch on the given number of threads.", "N");
+    options.optopt("L", "listen", "The address the http server listens on.", "ADDR");
+    options.optopt("S", "static", "The directory of the player's files for the http server.", "DIR");
+    options.optopt("D", "data-dir", "The directory the http server keeps its puzzles in.", "DIR");
+    options.optopt("R", "seed", "Generate the same puzzle every time for the given seed.", "N");
+    options.optopt("W", "density", "The chance of each square of a generated puzzle being a wall.",
+                   "D");
+    options.optopt("m", "symmetry", "Make the walls of a generated puzzle symmetric.",
+                   "none|rotate180|rotate90|horizontal|vertical|diagonal");
+    options.optflag("M", "symmetric-clues", "Make where the clues of a generated puzzle are symmetric too.");
+    options.optopt("G", "difficulty", "Search for a generated puzzle of the given difficulty.",
+                   "easy|medium|hard|expert");
+    options.optopt("E", "time-limit", "Search for the difficulty for at most this long.", "SECONDS");
+    options.optopt("w", "walls", "Find clues for the walls in the file instead of laying out new ones.",
+                   "FILE");
+    options.optflag("x", "redundant", "List the clues that could each be taken away.");
+    options.optflag("n", "minimize", "Take clues away until every one left is needed.");
+    options.optflag("a", "suggest", "Suggest clues that would make the solution unique.");
+    options.optflag("k", "backbone", "Show which squares are lights in every solution, in none or in some.");
     options.optflag("h", "help", "Print the usage");
     let matches = match options.parse(&args[1..]) {
         Ok(x) => x,
-        Err(y) => { panic!(y.to_string()) }
+        Err(y) => { panic!("{}", y) }
     };
     if matches.opt_present("h") {
         print_usage(&args[0], &options);
@@ -35,42 +129,567 @@ fn main() {
 
     let pretty_print = matches.opt_present("p");
     let unique_only = matches.opt_present("u");
+    let grade_only = matches.opt_present("g");
+    let certify_only = matches.opt_present("c");
+    let redundant_only = matches.opt_present("x");
+    let minimize = matches.opt_present("n");
+    let suggest = matches.opt_present("a");
+    let backbone_only = matches.opt_present("k");
+    let print_url = matches.opt_present("o");
+    let print_id = matches.opt_present("t");
+    let print_code = matches.opt_present("s");
+    let lookahead_depth = matches.opt_str("l").map_or(0, |x| x.parse::<usize>()
+        .unwrap_or_else(|e| panic!("Invalid lookahead depth {}: {}", x, e)));
+    let coord_style = matches.opt_str("C").map_or(CoordStyle::Flat, |x| x.parse::<CoordStyle>()
+        .unwrap_or_else(|e| panic!("{}", e)));
+    let format = matches.opt_str("f").map_or(Format::Text, |x| x.parse::<Format>()
+        .unwrap_or_else(|e| panic!("{}", e)));
+    let dialect = matches.opt_str("d").map_or(Dialect::Native, |x| x.parse::<Dialect>()
+        .unwrap_or_else(|e| panic!("{}", e)));
+
+    if matches.free.first().is_some_and(|x| x == "serve") {
+        let mut server = Server::new(dpll::solve);
+        if let Err(e) = server.serve(io::stdin().lock(), io::stdout().lock()) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if matches.free.first().is_some_and(|x| x == "http") {
+        let address = matches.opt_str("L").unwrap_or_else(|| "127.0.0.1:5000".to_string());
+        let static_dir = matches.opt_str("S").unwrap_or_else(|| "static".to_string());
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_nanos() as u64);
+        let app = App::new(static_dir.into(), matches.opt_str("D").map(|x| x.into()), seed, dpll::solve)
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+        eprintln!("Listening on http://{}", address);
+        if let Err(e) = http::run(app, &address) {
+            eprintln!("{}: {}", address, e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if matches.free.first().is_some_and(|x| x == "generate") {
+        let mut options = GenerateOptions::default();
+        if let Some(size) = matches.free.get(1) {
+            let (height, width) = parse_size(size).unwrap_or_else(|e| panic!("{}", e));
+            options.height = height;
+            options.width = width;
+        }
+        if let Some(density) = matches.opt_str("W") {
+            options.density = density.parse::<f64>().ok().filter(|x| (0.0..=1.0).contains(x))
+                .unwrap_or_else(|| panic!("Invalid density {}, expected 0 to 1", density));
+        }
+        if let Some(symmetry) = matches.opt_str("m") {
+            options.symmetry = symmetry.parse::<Symmetry>().unwrap_or_else(|e| panic!("{}", e));
+        }
+        options.symmetric_clues = matches.opt_present("M");
+        if let Some(difficulty) = matches.opt_str("G") {
+            options.difficulty = Some(difficulty.parse::<grade::Difficulty>().unwrap_or_else(|e| panic!("{}", e)));
+        }
+        if let Some(seconds) = matches.opt_str("E") {
+            options.time_limit = Duration::from_secs(seconds.parse::<u64>()
+                .unwrap_or_else(|e| panic!("Invalid time limit {}: {}", seconds, e)));
+        }
+        let seed = matches.opt_str("R").map_or_else(
+            || {
+                let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_nanos() as u64);
+                eprintln!("Seed: {}", seed);
+                seed
+            },
+            |x| x.parse::<u64>().unwrap_or_else(|e| panic!("Invalid seed {}: {}", x, e)));
+        let generated = match matches.opt_str("w") {
+            Some(path) => read_layout(&path, format, dialect).and_then(|layout| {
+                generate::generate_for_layout(&layout, &options, &mut Rng::new(seed), dpll::solve)
+            }),
+            None => generate::generate_puzzle(&options, &mut Rng::new(seed), dpll::solve),
+        };
+        match generated {
+            Ok(generated) => {
+                if let Some(difficulty) = options.difficulty.filter(|&x| x != generated.difficulty) {
+                    eprintln!("No {} puzzle found in {} seconds, the closest is {}",
+                              difficulty, options.time_limit.as_secs(), generated.difficulty);
+                }
+                if !generated.minimal {
+                    eprintln!("Some clues could each be taken away and leave the solution unique");
+                }
+                print!("{}", format_puzzle(&generated.puzzle, format));
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(path) = matches.opt_str("B") {
+        let report = matches.opt_str("r").unwrap_or_else(|| "jsonl".to_string());
+        let jobs = matches.opt_str("j").map_or_else(
+            || thread::available_parallelism().map_or(1, |x| x.get()),
+            |x| x.parse::<usize>().unwrap_or_else(|e| panic!("Invalid number of jobs {}: {}", x, e)));
+        match run_batch(&path, format, dialect, &report, jobs, lookahead_depth) {
+            Ok(true) => {},
+            Ok(false) => { process::exit(1); },
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(prefix) = matches.opt_str("b") {
+        let title = matches.opt_str("T");
+        if let Err(e) = write_booklet(&prefix, title, &matches.free, format, dialect) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let parsed = match matches.opt_str("i") {
+        Some(url) => puzzlink::decode_url(&url),
+        None => parse_input(&read_input().unwrap(), format, dialect),
+    };
+    let parsed = parsed.unwrap_or_else(|e| {
+        eprintln!("Invalid puzzle: {}", e);
+        process::exit(1);
+    });
+    if print_url {
+        println!("{}", puzzlink::encode_url(&parsed));
+        return;
+    }
+    if print_id {
+        println!("{}", tatham::encode_game_id(&parsed));
+        return;
+    }
+    if print_code {
+        println!("{}", share_code::encode_share_code(&parsed));
+        return;
+    }
+    let explain_loc = matches.opt_str("e").map(|x| coord_style.parse(&parsed, &x)
+        .unwrap_or_else(|e| panic!("{}", e)));
+    let highlights = matches.opt_str("H").map_or(Vec::new(), |locs| {
+        parse_locations(&parsed, &locs, coord_style).unwrap_or_else(|e| panic!("{}", e))
+    });
+    let svg_options = matches.opt_str("v").map(|x| svg_options_for(&x, highlights.clone())
+        .unwrap_or_else(|e| panic!("{}", e)));
+    let mut grid = utils::precompute_data(parsed);
 
-    let (height, width) = read_grid_dims().unwrap();
-    let grid_str = read_grid_string().unwrap();
+    if let Some(options) = svg_options {
+        if options.show_solution {
+            solver::solve_puzzle_with_lookahead(&mut grid, solve_sat_with_glucose, lookahead_depth)
+                .unwrap();
+        }
+        print!("{}", svg::render_svg(&grid, &options));
+        return;
+    }
 
-    let mut grid = utils::precompute_data(
-            utils::get_grid_from_string(&grid_str, height, width).unwrap());
+    if let Some(loc) = explain_loc {
+        print_explanation(&mut grid, loc, lookahead_depth, coord_style);
+        return;
+    }
+    if grade_only {
+        print_grade(&grid, format);
+        return;
+    }
+    if certify_only {
+        if !print_certification(&grid, lookahead_depth, format, coord_style) {
+            process::exit(1);
+        }
+        return;
+    }
+    if redundant_only {
+        print_redundant_clues(grid.grid(), format, coord_style);
+        return;
+    }
+    if backbone_only {
+        print_backbone(grid.grid(), format);
+        return;
+    }
+    if suggest {
+        print_clue_suggestions(grid.grid(), format, coord_style);
+        return;
+    }
+    if minimize {
+        match clues::minimize_clues(grid.grid(), solve_sat_with_glucose) {
+            Ok(puzzle) => { print!("{}", format_puzzle(&puzzle, format)); },
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-    let (light_locs, is_uniq) = solver::solve_puzzle(&mut grid, solve_sat_with_glucose).unwrap();
-    if pretty_print {
+    if format == Format::Json {
+        let solution = solver::solve_puzzle_with_stats(
+            &mut grid, solve_sat_with_glucose, lookahead_depth).unwrap();
+        println!("{}", serde_json::to_string(&solution).unwrap());
+        return;
+    }
+    let (light_locs, is_uniq) = solver::solve_puzzle_with_lookahead(
+        &mut grid, solve_sat_with_glucose, lookahead_depth).unwrap();
+    if format == Format::Pzprv3 && !unique_only {
+        print!("{}", pzprv3::encode_file(grid.grid()));
+    }
+    else if pretty_print && io::stdout().is_terminal() {
+        let options = TerminalOptions {
+            color: env::var_os(NO_COLOR_ENV_NAME).is_none(),
+            highlights,
+        };
+        print!("{}", terminal::render_terminal(&grid, &options));
+        println!("Unique solution: {}", is_uniq);
+    }
+    else if pretty_print {
         println!("{}", utils::print_griddata_to_string(&grid, true));
         println!("Unique solution: {}", is_uniq);
     }
     else {
         if !unique_only {
-            for loc in light_locs {
-                print!("{} ", loc);
+            for light in light_locs {
+                print!("{} ", coord_style.format(grid.grid(), light));
             }
-            println!("");
+            println!();
         }
         println!("{}", if is_uniq { 1 } else { 0 });
     }
 }
 
+fn parse_input(input: &str, format: Format, dialect: Dialect) -> Result<solver::Grid, String> {
+    match format {
+        Format::Text => parser::parse_puzzle(input, dialect).map_err(|e| e.to_string()),
+        Format::Pzprv3 => pzprv3::decode_file(input).map_err(|e| e.to_string()),
+        Format::Tatham => tatham::decode_game_id(input),
+        Format::Code => share_code::decode_share_code(input),
+        Format::Json => serde_json::from_str(input).map_err(|e| e.to_string()),
+    }
+}
+
+/// Reads a size written as HEIGHTxWIDTH, or as a single number for a square.
+fn parse_size(size: &str) -> Result<(i32, i32), String> {
+    let parse = |x: &str| x.parse::<i32>().ok().filter(|&x| x > 0)
+        .ok_or_else(|| format!("Invalid size {}, expected HEIGHTxWIDTH", size));
+    match size.split_once('x') {
+        Some((height, width)) => Ok((parse(height)?, parse(width)?)),
+        None => parse(size).map(|x| (x, x)),
+    }
+}
+
+/// Reads a wall layout from a PBM bitmap, or from a puzzle in the given format.
+fn read_layout(path: &str, format: Format, dialect: Dialect) -> Result<solver::Grid, String> {
+    let data = fs::read(path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+    let layout = if pbm::is_pbm(&data) {
+        pbm::decode_pbm(&data)
+    } else {
+        parse_input(&String::from_utf8_lossy(&data), format, dialect)
+    };
+    layout.map_err(|e| format!("Invalid layout in {}: {}", path, e))
+}
+
+/// Writes a puzzle the way parse_input reads it back.
+fn format_puzzle(grid: &solver::Grid, format: Format) -> String {
+    match format {
+        Format::Text => {
+            let puzzle = Puzzle::from(grid.clone());
+            format!("{} {}\n{}\n", puzzle.height, puzzle.width, puzzle.rows.join("\n"))
+        },
+        Format::Pzprv3 => pzprv3::encode_file(grid),
+        Format::Tatham => format!("{}\n", tatham::encode_game_id(grid)),
+        Format::Code => format!("{}\n", share_code::encode_share_code(grid)),
+        Format::Json => format!("{}\n", serde_json::to_string(grid).unwrap()),
+    }
+}
+
+/**
+ * Reads each of the puzzle files, and writes a booklet of them to files named
+ * PREFIX-1.svg, PREFIX-2.svg and so on, printing the name of each file.
+ */
+fn write_booklet(prefix: &str, title: Option<String>, paths: &[String], format: Format,
+                 dialect: Dialect) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("No puzzle files given for the booklet".to_string());
+    }
+    let mut puzzles = Vec::with_capacity(paths.len());
+    for path in paths {
+        let mut input = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut input))
+            .map_err(|e| format!("Error reading {}: {}", path, e))?;
+        let grid = parse_input(&input, format, dialect)
+            .map_err(|e| format!("Invalid puzzle in {}: {}", path, e))?;
+        let name = Path::new(path).file_stem().map_or(path.clone(), |x| x.to_string_lossy().into_owned());
+        puzzles.push(BookletPuzzle::new(&name, utils::precompute_data(grid), solve_sat_with_glucose)?);
+    }
+
+    let mut options = BookletOptions::default();
+    if let Some(title) = title {
+        options.title = title;
+    }
+    for (idx, page) in booklet::render_booklet(&puzzles, &options).iter().enumerate() {
+        let filename = format!("{}-{}.svg", prefix, idx + 1);
+        File::create(&filename).and_then(|mut f| f.write_all(page.as_bytes()))
+            .map_err(|e| format!("Error writing {}: {}", filename, e))?;
+        println!("{}", filename);
+    }
+    Ok(())
+}
+
+/**
+ * Solves every puzzle in the collection at path, or in every .grid file in
+ * the directory at path, and prints a record for each in the given report
+ * format. Returns whether every puzzle was solved.
+ */
+fn run_batch(path: &str, format: Format, dialect: Dialect, report: &str, jobs: usize,
+             lookahead_depth: usize) -> Result<bool, String> {
+    if report != "jsonl" && report != "csv" {
+        return Err(format!("Unknown report format {}", report));
+    }
+    let read = |path: &Path| fs::read_to_string(path)
+        .map_err(|e| format!("Error reading {}: {}", path.display(), e));
+    let mut puzzles = Vec::new();
+    if Path::new(path).is_dir() {
+        let mut files = fs::read_dir(path)
+            .map_err(|e| format!("Error reading {}: {}", path, e))?
+            .filter_map(|x| x.ok().map(|x| x.path()))
+            .filter(|x| x.extension().is_some_and(|ext| ext == "grid"))
+            .collect::<Vec<_>>();
+        files.sort();
+        for file in files {
+            let id = file.file_name().unwrap().to_string_lossy().into_owned();
+            puzzles.push(BatchPuzzle { id, puzzle: parse_input(&read(&file)?, format, dialect) });
+        }
+    } else {
+        let one_per_line = matches!(format, Format::Tatham | Format::Code | Format::Json);
+        for (line, text) in batch::split_collection(&read(Path::new(path))?, one_per_line) {
+            let id = format!("{}:{}", path, line);
+            puzzles.push(BatchPuzzle { id, puzzle: parse_input(&text, format, dialect) });
+        }
+    }
+
+    let records = batch::solve_batch(&puzzles, solve_sat_with_glucose, lookahead_depth, jobs);
+    if report == "csv" {
+        println!("{}", batch::CSV_HEADER);
+    }
+    for record in records.iter() {
+        if report == "csv" {
+            println!("{}", record.to_csv());
+        } else {
+            println!("{}", serde_json::to_string(record).unwrap());
+        }
+    }
+    Ok(records.iter().all(|x| x.outcome == Outcome::Solved))
+}
+
+/// The SVG options for a view named on the command line.
+fn svg_options_for(view: &str, highlights: Vec<Coord>) -> Result<SvgOptions, String> {
+    let (show_solution, show_beams) = match view {
+        "puzzle" => (false, false),
+        "solution" => (true, false),
+        "beams" => (true, true),
+        _ => { return Err(format!("Unknown SVG view {}", view)); }
+    };
+    Ok(SvgOptions { show_solution, show_beams, highlights, ..SvgOptions::default() })
+}
+
+/**
+ * Reads a list of squares in the given style, separated by spaces or
+ * semicolons. Flat indices may also be separated by commas.
+ */
+fn parse_locations(grid: &solver::Grid, locs: &str, style: CoordStyle) -> Result<Vec<Coord>, String> {
+    let is_separator = |c: char| c.is_whitespace() || c == ';' || (style == CoordStyle::Flat && c == ',');
+    locs.split(is_separator).filter(|x| !x.is_empty()).map(|x| style.parse(grid, x)).collect()
+}
+
 fn print_usage(progname: &str, opts: &Options) {
     let desc = format!("\
-    Usage: {} [-p|--pretty-print]
+    Usage: {0} serve
+           {0} http [-L|--listen ADDR] [-S|--static DIR] [-D|--data-dir DIR]
+           {0} generate [HEIGHTxWIDTH] [-R|--seed N] [-W|--density D] [-m|--symmetry NAME] [-M|--symmetric-clues] [-G|--difficulty LEVEL [-E|--time-limit SECONDS]] [-w|--walls FILE] [-f|--format FORMAT]
+           {0} [-p|--pretty-print] [-f|--format FORMAT] [-d|--dialect NAME] [-i|--url URL] [-o|--print-url] [-t|--print-id] [-s|--print-code] [-v|--svg VIEW] [-H|--highlight LOCS] [-b|--booklet PREFIX [-T|--title TITLE] FILE...] [-g|--grade] [-c|--certify] [-x|--redundant] [-n|--minimize] [-a|--suggest] [-k|--backbone] [-e|--explain LOC] [-l|--lookahead DEPTH] [-C|--coords STYLE] [-B|--batch PATH [-r|--report jsonl|csv] [-j|--jobs N]]
 
     Takes a puzzle to solve from standard input, solves it, and outputs the solution.
     Input format: First line consists of two numbers separated by a space,
     corresponding to the height and width of the puzzle, respectively.
-    Subsequent lines are interpreted as a string, where each non-whitespace character
-    corresponds to a square on the grid in row-major order. The meaning of characters
-    is as follows:
-    X -- Solid block
-    _ -- Empty block
+    Each subsequent line is a row of the puzzle, where each character
+    corresponds to a square. The meaning of characters is as follows:
+    X, B -- Solid block
+    _, . -- Empty block
     0, 1, 2, 3, 4 -- Solid blocks that carry a surrounding lights constraint
+    The squares may also all be given on one line, in row-major order. If the
+    first line is left out, the dimensions are taken from the rows. Blank lines
+    and lines starting with % are ignored.
+
+    With --dialect plain, # is a solid block and - is an empty block, as in
+    most puzzle collections. Malformed input is reported with its line and
+    column, and the exit status is 1.
+
+    With --format pzprv3, the puzzle is read as a pzprv3 file, as saved by
+    pzpr.js and puzz.link, and the solution is written in the same format,
+    with # for lights and + for squares that can't hold a light. Lights and
+    marks in the input are kept.
+
+    With --format tatham, the puzzle is read as a game ID from the Light Up game
+    in Simon Tatham's Portable Puzzle Collection, such as 7x7:b1c0B... With
+    --print-id, the puzzle is not solved, and a game ID for it is printed
+    instead. The solution is printed as usual, since game IDs can't hold one.
+
+    With --format code, the puzzle is read as a puzzle code, a short string of
+    letters, digits, - and _ with a built-in checksum that is easy to paste
+    into chat or a URL. With --print-code, the puzzle is not solved, and a
+    puzzle code for it is printed instead. Like game IDs, codes only hold the
+    walls and clues.
+
+    With --format json, the puzzle is read as a JSON object such as
+    {{\"height\": 3, \"width\": 3, \"rows\": [\"___\", \"_3_\", \"_X_\"]}}
+    where each row uses the characters above. The result is written as a
+    single line of JSON with these fields:
+    height, width -- The dimensions of the puzzle
+    lights -- The lights of a solution, as [row, column] pairs
+    unique -- Whether the solution is unique
+    second_solution -- The lights of another solution, or null if it is unique
+    stats -- An object with solved_by_rules, deductions, rounds, solver_calls,
+             variables, clauses and elapsed_ms
+    --grade and --certify write their results as JSON objects as well, with
+    the same field names as their text output.
+
+    With --redundant, the puzzle is not solved, and the clues that could each
+    be taken away on its own with the solution staying unique are listed on
+    one line instead, written as described under --coords, or with --format
+    json as {{\"redundant\": [[row, column], ...]}}. Taking two of them away
+    together may leave more than one solution. With --minimize, clues are
+    taken away in row-major order for as long as the solution stays unique,
+    and the puzzle that is left, where every clue is needed, is written in
+    the given format. Both fail if the solution isn't unique to begin with.
+    With --suggest, the solutions of a puzzle with more than one are told
+    apart by numbering some of its bare walls instead. The walls whose
+    clue would rule out another solution are listed one per line, best
+    first, as \"SQUARE: CLUE rules out N\", followed by \"Add: \" and a set
+    of clues, each as SQUARE=CLUE, that together make the solution unique. With --format json,
+    they are written as {{\"solution\": [[row, column], ...], \"alternatives\":
+    N, \"all_alternatives\": BOOL, \"candidates\": [...], \"clues\": [...]}},
+    where each clue is an object with loc, clue and eliminates.
+
+    With --backbone, the puzzle is not solved, and every empty square is
+    instead checked against all of its solutions at once. The puzzle is
+    written back with a * where every solution has a light, a ^ where none
+    has one, and a ? where only some do, so the ?s show where it is
+    ambiguous. With --format json, the squares are written as
+    {{\"forced\": [[row, column], ...], \"forbidden\": [...], \"free\": [...]}}.
+    It fails if the puzzle has no solution.
+
+    With --url, the puzzle is read from a puzz.link or pzprv3 URL such as
+    https://puzz.link/p?lightup/10/10/... instead of standard input.
+    With --print-url, the puzzle is not solved, and a puzz.link URL for it is
+    printed instead. Only walls and clues are kept in URLs.
+
+    With --svg, an SVG drawing is written instead of the usual output. The view
+    is one of these:
+    puzzle -- Only the walls and clues
+    solution -- The puzzle solved, with bulbs for lights and shading for lit squares
+    beams -- The solution along with the beam each light casts
+    --highlight takes a list of squares, written as described under --coords
+    and separated by spaces or semicolons, to outline in the drawing, such as
+    squares with errors or hints. Flat indices may also be separated by commas.
+
+    With --booklet, each FILE is read as a puzzle in the given format, and the
+    puzzles are laid out six to an A4 page as SVG files named PREFIX-1.svg,
+    PREFIX-2.svg and so on, whose names are printed. Each puzzle is captioned
+    with its number, its file name and its difficulty, and the pages of
+    puzzles are followed by answer key pages with the solutions. --title sets
+    the heading printed at the top of each page.
+
+    With --batch, every puzzle in the file at PATH is solved, or every .grid
+    file if PATH is a directory, on as many threads as --jobs gives, by default
+    one per processor. Puzzles in a file are read in the given format and
+    separated by blank lines, except that with --format tatham, code or json
+    each line is a puzzle. One record per puzzle is written, in the order of
+    the puzzles, as a line of JSON or, with --report csv, as CSV with a header.
+    Records have these fields:
+    id -- The file name, or the file and the line the puzzle starts on
+    height, width -- The dimensions of the puzzle, or 0 if it can't be read
+    outcome -- One of solved, unsolved or invalid
+    unique -- Whether the solution is unique, empty or null if not solved
+    solved_by_rules -- Whether the rules solved it without the SAT solver
+    solver_calls, variables, clauses -- The calls to and size of the SAT problem
+    elapsed_ms -- The time taken to solve the puzzle
+    error -- Why the puzzle was not solved, if it wasn't
+    The exit status is 1 if any puzzle was not solved.
+
+    With serve, the program answers requests until standard input ends,
+    instead of solving a single puzzle, using the built-in SAT solver rather
+    than the one named by SAT_SOLVER. Each request is a line of JSON, such as
+    {{\"id\": 1, \"method\": \"unique\", \"puzzle\": {{\"height\": 1, \"width\": 2, \"rows\": [\"_1\"]}}}}
+    where the puzzle is written as for --format json. Each is answered with a
+    line of JSON holding the same id and either a result or an error message:
+    {{\"id\": 1, \"result\": {{\"unique\": true}}}}
+    The methods are these:
+    solve -- Answers with the same object as --format json
+    unique -- Answers with whether the solution is unique
+    verify -- Takes lights, a list of [row, column] pairs, and answers with
+              solved, and the conflicts, wrong_clues and unlit squares
+    grade -- Answers with the same object as --grade --format json
+    share -- Answers with the url, game_id and code of the puzzle, as printed
+             by --print-url, --print-id and --print-code
+    solve and unique take an optional lookahead depth. Solutions are kept
+    between requests, so asking about the same puzzle again is fast.
+
+    With http, the program serves the player over HTTP on the address given by
+    --listen, by default 127.0.0.1:5000, in place of the Python server. The
+    page and its files are served from the directory given by --static, by
+    default ./static, and puzzles are generated and checked with the built-in
+    SAT solver, so neither Python nor Glucose is needed. The routes are these:
+    GET / -- The player's index.html
+    GET /static/FILE -- A file from the static directory
+    GET /new_puzzle?height=H&width=W -- A new puzzle with a unique solution, 5x5
+                                        by default, as height, width, data,
+                                        url, game_id, code, ratio, minimal
+                                        and puzzle_id
+    POST /check_puzzle -- Takes {{\"id\": ID, \"lights\": [[row, column], ...]}} and
+                          answers with a result of soln-complete, soln-unknown
+                          if the lights can still be completed to a solution,
+                          or soln-incorrect
+    Any solution of a puzzle is accepted. With --data-dir, each puzzle handed
+    out is saved there as ID.json, in the form --format json reads, and the
+    saved puzzles are loaded again at startup.
+
+    With generate, a new puzzle with a unique solution is written in the given
+    format instead of solving one, 5x5 unless a size such as 10x7 (height by
+    width) or 8 is given. Random walls are laid out, each square being a wall
+    with the chance given by --density, by default 0.35, and clues are added
+    and then taken away for as long as the solution stays unique, using the
+    built-in SAT solver, until every clue left is needed. The same --seed
+    always gives the same puzzle. Without one, a seed is picked and printed
+    to standard error.
+    --symmetry lays the walls out symmetrically, in one of these ways:
+    none -- Each square is a wall or not on its own
+    rotate180 -- The same when turned upside down
+    rotate90 -- The same when turned a quarter, for square puzzles only
+    horizontal -- The left half mirrors the right half
+    vertical -- The top half mirrors the bottom half
+    diagonal -- Mirrored along the diagonal from the top left, for square
+                puzzles only
+    With --symmetric-clues, the walls that are symmetric to each other either
+    all carry clues or none do, though their numbers may differ. Clues are
+    then taken away together, so some may not be needed on their own, in
+    which case a note on standard error says so.
+    With --difficulty, clues are only taken away while the puzzle grades as
+    that difficulty or easier, as --grade would grade it, and new layouts are
+    tried until one grades exactly as asked. After --time-limit seconds, by
+    default 30, the closest puzzle found is written instead, with a note on
+    standard error.
+    With --walls, the walls are taken from FILE instead of being laid out at
+    random, and only clues are found for them, trying many ways of placing
+    the lights. FILE is either a puzzle in the given format, whose clues are
+    ignored, or a PBM bitmap, plain or raw, in which each black pixel is a
+    wall. The size and --density are ignored, and the walls must already
+    have the --symmetry asked for. If none of the ways of placing the lights
+    tried gives clues that make the solution unique, the exit status is 1,
+    though some untried way might have.
 
     Output will be produced on standard out.
     If --pretty-print is not specified, output will be a list of indices that contain
@@ -80,58 +699,232 @@ fn print_usage(progname: &str, opts: &Options) {
     If --pretty print is specified, output will be a formatted grid that contains a
     solution, formatted the same, but with the following possible characters:
     * -- Square that contains a light
-    # -- Square that has been lit\
+    # -- Square that has been lit
+    When standard out is a terminal, the grid is instead drawn in a border with
+    row and column numbers, lights as bulbs, lit squares as dots and walls as
+    blocks, coloured unless the NO_COLOR environment variable is set. The
+    squares given to --highlight are highlighted.
+
+    If --grade is specified, the puzzle is graded instead of solved. The output
+    lists the difficulty, whether the rules alone solve the puzzle, the number of
+    rule passes, how deep a search was needed, and how often each rule was used.
+
+    With --coords, squares are read and written in the given style, for the
+    list of lights, --explain, --highlight and --certify:
+    flat -- The index of the square in row-major order, such as 7 (the default)
+    0-based -- The row and column counting from 0, such as 2,1
+    1-based -- The row and column counting from 1, such as 3,2
+    JSON output always gives squares as [row, column] pairs counting from 0.
+
+    If --explain is specified, the puzzle is not solved. Instead, the rule
+    applications that force the state of the square LOC are printed in
+    the order they were made, each followed by the earlier steps it relies on.
+
+    If --certify is specified, the puzzle is not solved. Instead, each change the
+    rules make is checked by asking the SAT solver whether the original puzzle
+    can be solved with that change reversed. The first deduction that is not
+    forced is printed along with the board before it, and the exit status is 1.
+
+    If --lookahead is specified, whenever the rules get stuck, each open square is
+    tried both ways on a copy of the grid, and the state that leads to a
+    contradiction is ruled out. The copies use lookahead of one less depth.
+    This applies to solving, --explain and --certify.\
     ", progname);
 
     print!("{}", opts.usage(&desc));
 }
 
-fn read_grid_dims() -> Result<(i32, i32), String> {
-    let mut input_line = String::new();
-    try!(io::stdin().read_line(&mut input_line).map_err(|e| e.to_string()));
-    let maybe_dims = input_line.split_whitespace()
-        .map(|x| x.parse::<i32>())
-        .collect::<Result<Vec<_>, _>>();
-    let dims = try!(maybe_dims.map_err(|e| e.to_string()));
-    if dims.len() != 2 {
-        return Err(format!("Found {} dimensions instead of 2.", dims.len()));
+fn print_explanation(grid: &mut solver::GridData, loc: Coord, lookahead_depth: usize,
+                     style: CoordStyle) {
+    let trace = rules::populate_with_lookahead_traced(grid, lookahead_depth);
+    let square = |x: Coord| style.format(grid.grid(), x);
+    let chain = match trace.explain(loc) {
+        Some(x) => x,
+        None => {
+            println!("Square {} is not forced by the rules.", square(loc));
+            return;
+        }
+    };
+    for id in chain {
+        let deduction = &trace.deductions[id];
+        let changes = deduction.changes.iter()
+            .map(|&(idx, mark)| format!("{} {}", square(idx), mark))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let premises = deduction.premises.iter()
+            .map(|p| format!("#{}", p))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if premises.is_empty() {
+            println!("#{}: {} at {}: {}", id, deduction.rule, square(deduction.loc), changes);
+        } else {
+            println!("#{}: {} at {}: {} (from {})",
+                     id, deduction.rule, square(deduction.loc), changes, premises);
+        }
+    }
+}
+
+fn print_certification(grid: &solver::GridData, lookahead_depth: usize, format: Format,
+                       style: CoordStyle) -> bool {
+    let certificate = certify::certify_rules(grid, solve_sat_with_glucose, lookahead_depth)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    if format == Format::Json {
+        println!("{}", serde_json::to_string(&certificate).unwrap());
+        return certificate.unsound.is_none();
+    }
+    println!("Certified {} changes.", certificate.checked);
+    match certificate.unsound {
+        Some(unsound) => {
+            let deduction = &unsound.deduction;
+            println!("Unsound deduction #{}: {} at {} changed square {}.",
+                     unsound.index, deduction.rule, style.format(grid.grid(), deduction.loc),
+                     style.format(grid.grid(), unsound.square));
+            println!("Board before the deduction:{}", unsound.board);
+            false
+        },
+        None => true,
+    }
+}
+
+fn print_grade(grid: &solver::GridData, format: Format) {
+    let result = grade::grade_puzzle(grid).unwrap();
+    if format == Format::Json {
+        println!("{}", serde_json::to_string(&result).unwrap());
+        return;
+    }
+    println!("Difficulty: {}", result.difficulty);
+    println!("Solved by rules: {}", result.solved_by_rules);
+    println!("Solved by lookahead: {}", result.solved_by_lookahead);
+    println!("Rounds: {}", result.rounds);
+    if result.search_depth == grade::MAX_SEARCH_DEPTH {
+        println!("Search depth: {} or more", result.search_depth);
+    } else {
+        println!("Search depth: {}", result.search_depth);
+    }
+    println!("Search nodes: {}", result.search_nodes);
+    println!("Rules used:");
+    for (rule, count) in result.rule_counts.iter() {
+        println!("    {}: {}", rule, count);
+    }
+}
+
+/// Prints the squares of the clues that could each be taken away, on one line.
+fn print_redundant_clues(grid: &solver::Grid, format: Format, coord_style: CoordStyle) {
+    let redundant = clues::redundant_clues(grid, solve_sat_with_glucose).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if format == Format::Json {
+        println!("{}", serde_json::json!({"redundant": redundant}));
+        return;
     }
-    Ok((dims[0], dims[1]))
+    let squares: Vec<String> = redundant.iter().map(|&x| coord_style.format(grid, x)).collect();
+    println!("{}", squares.join(" "));
 }
 
-fn read_grid_string() -> Result<String, String> {
-    let mut grid_raw = String::new();
-    try!(io::stdin().read_to_string(&mut grid_raw).map_err(|e| e.to_string()));
-    Ok(grid_raw)
+/// Prints the puzzle with the squares that are settled across all solutions marked.
+fn print_backbone(grid: &solver::Grid, format: Format) {
+    let backbone = backbone::find_backbone(grid, solve_sat_with_glucose).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if format == Format::Json {
+        println!("{}", serde_json::json!({
+            "forced": backbone.with_status(backbone::Status::Forced),
+            "forbidden": backbone.with_status(backbone::Status::Forbidden),
+            "free": backbone.with_status(backbone::Status::Free),
+        }));
+        return;
+    }
+    print!("{}", backbone::render_backbone(grid, &backbone));
 }
 
+/// Prints the walls that could be numbered to tell the solutions apart.
+fn print_clue_suggestions(grid: &solver::Grid, format: Format, coord_style: CoordStyle) {
+    let suggestions = clues::suggest_clues(grid, solve_sat_with_glucose).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if format == Format::Json {
+        let to_json = |list: &[clues::ClueSuggestion]| list.iter().map(|x| serde_json::json!({
+            "loc": x.loc,
+            "clue": x.clue,
+            "eliminates": x.eliminates,
+        })).collect::<Vec<_>>();
+        println!("{}", serde_json::json!({
+            "solution": suggestions.solution,
+            "alternatives": suggestions.alternatives,
+            "all_alternatives": suggestions.all_alternatives,
+            "candidates": to_json(&suggestions.candidates),
+            "clues": to_json(&suggestions.clues),
+        }));
+        return;
+    }
+    for candidate in &suggestions.candidates {
+        println!("{}: {} rules out {}", coord_style.format(grid, candidate.loc), candidate.clue,
+                 candidate.eliminates);
+    }
+    let added: Vec<String> = suggestions.clues.iter()
+        .map(|x| format!("{}={}", coord_style.format(grid, x.loc), x.clue)).collect();
+    println!("Add: {}", added.join(" "));
+}
+
+fn read_input() -> Result<String, String> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).map_err(|e| e.to_string())?;
+    Ok(input)
+}
+
+/// Counts calls to the SAT solver, so each call can use its own files.
+static SOLVER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
 fn solve_sat_with_glucose(cnf: &cnf_format::CnfFormula) -> Result<Vec<i32>, String> {
+    // Batches are solved on several threads, so the files can't be shared.
+    let call = SOLVER_CALLS.fetch_add(1, Ordering::SeqCst);
+    let cnf_out = format!("{}-{}-{}.cnf", CNF_OUT, process::id(), call);
+    let result_in = format!("{}-{}-{}.cnf", RESULT_IN, process::id(), call);
+    let result = run_glucose(cnf, &cnf_out, &result_in);
+    let _ = fs::remove_file(&cnf_out);
+    let _ = fs::remove_file(&result_in);
+    result
+}
+
+fn run_glucose(cnf: &cnf_format::CnfFormula, cnf_out: &str, result_in: &str)
+        -> Result<Vec<i32>, String> {
     {
-        let mut cnf_file = try!(File::create(CNF_OUT)
-                                .map_err(|e| format!("Error creating CNF file: {}",
-                                                     e.to_string())));
-        try!(cnf.write_to_file(&mut cnf_file)
-             .map_err(|e| format!("Error writing to file: {}", e.to_string())));
+        let mut cnf_file = File::create(cnf_out)
+            .map_err(|e| format!("Error creating CNF file: {}", e))?;
+        cnf.write_to_file(&mut cnf_file)
+            .map_err(|e| format!("Error writing to file: {}", e))?;
     }
-    let sat_solver_path =
-        try!(env::var(SAT_SOLVER_ENV_NAME)
-             .map_err(|x| format!("Error finding SAT solver: {:?}", x)));
+    let sat_solver_path = env::var(SAT_SOLVER_ENV_NAME)
+        .map_err(|x| format!("Error finding SAT solver: {:?}", x))?;
 
     let sat_result = Command::new(sat_solver_path)
-        .arg(CNF_OUT)
-        .arg(RESULT_IN)
+        .arg(cnf_out)
+        .arg(result_in)
         .output();
 
-    try!(sat_result.map_err(|e| format!("Error executing SAT solver: {}", e.to_string())));
-    read_variable_mapping(RESULT_IN)
+    sat_result.map_err(|e| format!("Error executing SAT solver: {}", e))?;
+    read_variable_mapping(result_in)
 }
 
 fn read_variable_mapping(filepath: &str) -> Result<Vec<i32>, String> {
-    let mut infile = try!(File::open(filepath).map_err(|e| e.to_string()));
+    let mut infile = File::open(filepath).map_err(|e| e.to_string())?;
     let mut result_string = String::new();
     let mut result = Vec::new();
-    try!(infile.read_to_string(&mut result_string).map_err(|e| e.to_string()));
+    infile.read_to_string(&mut result_string).map_err(|e| e.to_string())?;
     for var_str in result_string.split_whitespace() {
+        // The model may be preceded by SAT, or be UNSAT on its own.
+        if var_str == solver::UNSAT {
+            return Err(solver::UNSAT.to_string());
+        }
+        if var_str == "SAT" {
+            continue;
+        }
         if let Ok(v) = var_str.trim().parse::<i32>() {
             if v != 0 {
                 result.push(v);